@@ -11,7 +11,8 @@
 use anyhow::{bail, Result};
 use clap::Parser;
 
-use hybrid_rag::chunking::ChunkingConfig;
+use hybrid_rag::chunking::{ChunkingConfig, ChunkingStrategy};
+use hybrid_rag::RagConfig;
 use hybrid_rag::ingest::DocumentIndexer;
 
 #[derive(Parser, Debug)]
@@ -54,13 +55,58 @@ struct Args {
     #[arg(long, env = "QDRANT_PORT", default_value_t = 6334)]
     qdrant_port: u16,
 
-    /// Max tokens per chunk
-    #[arg(long, default_value_t = 350)]
-    max_tokens: usize,
+    /// Max tokens per chunk (default: `chunking.max_tokens` from --config, else 350)
+    #[arg(long)]
+    max_tokens: Option<usize>,
+
+    /// Overlap tokens between chunks (default: `chunking.overlap_tokens` from --config, else 60)
+    #[arg(long)]
+    overlap_tokens: Option<usize>,
+
+    /// Carry overlap across adjacent blocks, not only inside a split block
+    #[arg(long)]
+    overlap_across_blocks: bool,
+
+    /// Do not prepend the section heading path to passages before embedding
+    #[arg(long)]
+    no_embed_headings: bool,
+
+    /// Chunker: blocks | sentence-window | token-window | semantic
+    /// (default: `chunking.strategy` from --config, else blocks)
+    #[arg(long, alias = "strategy")]
+    chunker: Option<ChunkingStrategy>,
 
-    /// Overlap tokens between chunks
-    #[arg(long, default_value_t = 60)]
-    overlap_tokens: usize,
+    /// Semantic strategy: breakpoint percentile of adjacent-sentence distances
+    /// (default: `chunking.semantic_breakpoint_percentile` from --config, else 95)
+    #[arg(long)]
+    breakpoint_percentile: Option<f32>,
+
+    /// Semantic strategy: neighbouring sentences embedded together on each side
+    /// (default: `chunking.semantic_buffer_size` from --config, else 1)
+    #[arg(long)]
+    buffer_size: Option<usize>,
+
+    /// CSV/JSON/JSONL: index every record as a separate document (implied by the
+    /// --record-* flags; otherwise such files are indexed as plain text)
+    #[arg(long)]
+    records: bool,
+
+    /// CSV/JSON/JSONL: fields that form the embedded text (comma-separated; default: all
+    /// fields except the id and metadata fields)
+    #[arg(long, value_delimiter = ',')]
+    record_text_fields: Vec<String>,
+
+    /// CSV/JSON/JSONL: fields stored as payload metadata (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    record_meta_fields: Vec<String>,
+
+    /// CSV/JSON/JSONL: field with a stable record id
+    #[arg(long)]
+    record_id_field: Option<String>,
+
+    /// Jupyter notebooks: index text outputs of code cells too
+    #[arg(long)]
+    notebook_outputs: bool,
 
     /// Config file path (TOML)
     #[arg(long)]
@@ -70,21 +116,57 @@ struct Args {
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
-
-    // Chunking config
-    let chunking_config = ChunkingConfig {
-        max_tokens: args.max_tokens,
-        overlap_tokens: args.overlap_tokens,
-        approx_chars_per_token: 4.0,
-        hard_max_bytes: 96 * 1024,
-    };
+    let rag_config = args.config.as_deref().map(RagConfig::from_file).transpose()?;
+
+    // Chunking config: `[chunking]` from --config, explicit CLI flags override it
+    let mut chunking_config: ChunkingConfig = rag_config
+        .as_ref()
+        .map(|c| c.chunking.clone())
+        .unwrap_or_default()
+        .into();
+    if let Some(max_tokens) = args.max_tokens {
+        chunking_config.max_tokens = max_tokens;
+    }
+    if let Some(overlap_tokens) = args.overlap_tokens {
+        chunking_config.overlap_tokens = overlap_tokens;
+    }
+    chunking_config.overlap_across_blocks |= args.overlap_across_blocks;
+    if args.no_embed_headings {
+        chunking_config.embed_headings = false;
+    }
+    if let Some(strategy) = args.chunker {
+        chunking_config.strategy = strategy;
+    }
+    if let Some(percentile) = args.breakpoint_percentile {
+        chunking_config.semantic_breakpoint_percentile = percentile;
+    }
+    if let Some(buffer_size) = args.buffer_size {
+        chunking_config.semantic_buffer_size = buffer_size;
+    }
 
     // Initialize indexer
     let qdrant_url = format!("http://{}:{}", args.qdrant_host, args.qdrant_port);
     let model_path = format!("{}/model.onnx", args.model_dir);
 
+    // Record mapping: CLI flags override `[records]` from --config
+    let mut record_mapping = rag_config.as_ref().map(|c| c.records.clone()).unwrap_or_default();
+    record_mapping.enabled |= args.records;
+    if !args.record_text_fields.is_empty() {
+        record_mapping.text_fields = args.record_text_fields;
+    }
+    if !args.record_meta_fields.is_empty() {
+        record_mapping.meta_fields = args.record_meta_fields;
+    }
+    if args.record_id_field.is_some() {
+        record_mapping.id_field = args.record_id_field;
+    }
+
+    let archive_limits = rag_config.as_ref().map(|c| c.archive).unwrap_or_default();
+    let mut notebook = rag_config.map(|c| c.notebook).unwrap_or_default();
+    notebook.include_outputs |= args.notebook_outputs;
+
     println!("🚀 Initializing indexer...");
-    let indexer = DocumentIndexer::new(
+    let mut indexer = DocumentIndexer::new(
         &qdrant_url,
         &model_path,
         &args.tokenizer_path,
@@ -92,6 +174,9 @@ async fn main() -> Result<()> {
         chunking_config,
     )
     .await?;
+    indexer.set_record_mapping(record_mapping);
+    indexer.set_notebook_config(notebook);
+    indexer.set_archive_limits(archive_limits);
 
     // Ensure collection exists
     indexer.ensure_collection().await?;
@@ -0,0 +1,242 @@
+// file: src/chunking/annotated.rs
+//
+// Формат ручной разметки сегментов (см. annotated_text.txt):
+//
+//   <<<BLOCK:0>>>
+//   ...текст блока...
+//   <<<END:0>>>
+//
+// Экспорт результатов parse_blocks/chunk_document и импорт размеченных
+// файлов как заранее сегментированных документов.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::{
+    emit_segment, locate_chunks, parse_blocks_from, parse_header, Block, BlockKind, Chunk, ChunkingConfig,
+    TokenCounter,
+};
+
+lazy_static! {
+    static ref RE_BLOCK_OPEN: Regex = Regex::new(r"^<<<BLOCK:(\d+)>>>\s*$").unwrap();
+    static ref RE_BLOCK_CLOSE: Regex = Regex::new(r"^<<<END:(\d+)>>>\s*$").unwrap();
+}
+
+/// Документ из размеченного файла: текст блоков, склеенный через пустую строку,
+/// и диапазоны каждого блока в этом тексте.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotatedDocument {
+    pub text: String,
+    pub segments: Vec<(usize, usize)>,
+    /// Те же блоки в исходном файле (текст блока — дословный срез исходника)
+    pub source_segments: Vec<(usize, usize)>,
+}
+
+impl AnnotatedDocument {
+    /// Смещение в `text` → смещение в исходном файле. Между блоками (склейка
+    /// `\n\n`) начало уходит к следующему блоку, конец — к предыдущему.
+    pub fn to_source(&self, offset: usize, is_end: bool) -> usize {
+        for (i, (&(s, e), &(src, _))) in self.segments.iter().zip(&self.source_segments).enumerate() {
+            if offset < s {
+                return match (is_end, i) {
+                    (true, 1..) => self.source_segments[i - 1].1,
+                    _ => src,
+                };
+            }
+            if offset <= e {
+                return src + (offset - s);
+            }
+        }
+        self.source_segments.last().map_or(0, |&(_, e)| e)
+    }
+}
+
+/// Блоки `parse_blocks` в размеченном формате.
+pub fn export_blocks(input: &str, blocks: &[Block]) -> String {
+    write_annotated(blocks.iter().map(|b| &input[b.start..b.end]))
+}
+
+/// Чанки в размеченном формате.
+pub fn export_chunks(chunks: &[Chunk]) -> String {
+    write_annotated(chunks.iter().map(|c| c.text.as_str()))
+}
+
+fn write_annotated<'a>(texts: impl Iterator<Item = &'a str>) -> String {
+    let mut out = String::new();
+    for (n, text) in texts.enumerate() {
+        if n > 0 {
+            out.push('\n');
+        }
+        out.push_str(&format!("<<<BLOCK:{n}>>>\n{}\n<<<END:{n}>>>\n", text.trim_end_matches('\n')));
+    }
+    out
+}
+
+/// Разбирает размеченный файл. `None`, если текст не в этом формате
+/// (первая непустая строка не `<<<BLOCK:n>>>` или маркеры не сходятся).
+pub fn parse_annotated(input: &str) -> Option<AnnotatedDocument> {
+    let mut text = String::new();
+    let mut segments = Vec::new();
+    let mut source_segments = Vec::new();
+    // Open block: its id and where its body starts in `input`
+    let mut open: Option<(String, usize)> = None;
+    let mut offset = 0;
+
+    for raw in input.split_inclusive('\n') {
+        let line_start = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(['\n', '\r']).trim_start_matches('\u{FEFF}');
+        match &open {
+            None => {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let caps = RE_BLOCK_OPEN.captures(line)?;
+                open = Some((caps[1].to_string(), offset));
+            }
+            Some((id, body_start)) => {
+                let Some(caps) = RE_BLOCK_CLOSE.captures(line) else { continue };
+                if &caps[1] != id.as_str() {
+                    return None;
+                }
+                // The body is kept verbatim so spans map back onto the source
+                let body = &input[*body_start..line_start];
+                let lead = body.len() - body.trim_start_matches(['\n', '\r']).len();
+                let body = body.trim_matches(['\n', '\r']);
+                if !body.trim().is_empty() {
+                    if !text.is_empty() {
+                        text.push_str("\n\n");
+                    }
+                    let start = text.len();
+                    text.push_str(body);
+                    segments.push((start, text.len()));
+                    let src = body_start + lead;
+                    source_segments.push((src, src + body.len()));
+                }
+                open = None;
+            }
+        }
+    }
+
+    if open.is_some() || segments.is_empty() {
+        return None;
+    }
+    Some(AnnotatedDocument {
+        text,
+        segments,
+        source_segments,
+    })
+}
+
+/// Начинается ли текст (например, первые байты большого файла) с маркера
+/// `<<<BLOCK:n>>>` — такой файл разбирается `parse_annotated`, а не потоком.
+pub fn looks_annotated(head: &str) -> bool {
+    head.lines()
+        .map(|l| l.trim_start_matches('\u{FEFF}'))
+        .find(|l| !l.trim().is_empty())
+        .is_some_and(|l| RE_BLOCK_OPEN.is_match(l.trim_end_matches('\r')))
+}
+
+/// Чанкинг заранее сегментированного документа: каждый размеченный блок —
+/// отдельный сегмент (мелкие блоки не склеиваются), большие режутся по структуре.
+/// Спаны и позиции чанков указывают в исходный файл `source`, из которого
+/// разобран `doc`, а не в склеенный `doc.text`.
+pub fn chunk_annotated(
+    doc_id: &str,
+    source: &str,
+    doc: &AnnotatedDocument,
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+) -> Vec<Chunk> {
+    let input = doc.text.as_str();
+    let mut chunks = Vec::new();
+    let mut heading_stack: Vec<(usize, String)> = Vec::new();
+
+    for &(s, e) in &doc.segments {
+        // Inner structure only drives kinds, headings and oversize splitting;
+        // a segment is never a whole file, so no front matter detection
+        let inner: Vec<Block> = parse_blocks_from(&input[s..e], 0)
+            .into_iter()
+            .map(|b| Block {
+                start: s + b.start,
+                end: s + b.end,
+                ..b
+            })
+            .collect();
+        if inner.is_empty() {
+            continue;
+        }
+        for b in inner.iter().filter(|b| b.kind == BlockKind::Header) {
+            if let Some((level, title)) = parse_header(&input[b.start..b.end]) {
+                heading_stack.retain(|(l, _)| *l < level);
+                heading_stack.push((level, title));
+            }
+        }
+        let segment: Vec<&Block> = inner.iter().collect();
+        emit_segment(doc_id, input, &segment, None, &heading_stack, cfg, tc, &mut chunks);
+    }
+
+    for chunk in &mut chunks {
+        chunk.start = doc.to_source(chunk.start, false);
+        chunk.end = doc.to_source(chunk.end, true).max(chunk.start);
+    }
+    locate_chunks(source, &mut chunks);
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let src = "<<<BLOCK:0>>>\n# Section 1\nShort.\n<<<END:0>>>\n\n\
+                   <<<BLOCK:1>>>\n## Section 2\nno punctuation here\n<<<END:1>>>\n";
+        let doc = parse_annotated(src).unwrap();
+        assert_eq!(doc.segments.len(), 2);
+        assert_eq!(&doc.text[doc.segments[0].0..doc.segments[0].1], "# Section 1\nShort.");
+
+        let tc = TokenCounter::approx(4.0);
+        let ch = chunk_annotated("doc::1", src, &doc, &ChunkingConfig::default(), &tc);
+        // Small hand-labelled blocks are not merged
+        assert_eq!(ch.len(), 2);
+        assert_eq!(ch[1].headings, vec!["Section 1", "Section 2"]);
+        assert_eq!(parse_annotated(&export_chunks(&ch)).unwrap().text, doc.text);
+    }
+
+    #[test]
+    fn test_spans_point_into_source() {
+        let src = "\u{FEFF}<<<BLOCK:0>>>\r\nПервый блок.\r\n<<<END:0>>>\r\n\r\n\
+                   <<<BLOCK:1>>>\n\n## Второй\nтекст\n<<<END:1>>>\n";
+        let doc = parse_annotated(src).unwrap();
+        let tc = TokenCounter::approx(4.0);
+        let ch = chunk_annotated("doc::1", src, &doc, &ChunkingConfig::default(), &tc);
+        assert_eq!(ch.len(), 2);
+        for c in &ch {
+            assert_eq!(&src[c.start..c.end], c.text);
+        }
+        assert_eq!((ch[0].pos.start_line, ch[0].pos.start_col), (2, 1));
+        assert_eq!((ch[1].pos.start_line, ch[1].pos.end_line), (7, 8));
+        assert_eq!(ch[1].pos.char_start, src[..ch[1].start].chars().count());
+    }
+
+    #[test]
+    fn test_segment_with_dashes_is_content() {
+        let src = "<<<BLOCK:0>>>\n---\ntitle: draft\n---\nBody text.\n<<<END:0>>>\n";
+        let doc = parse_annotated(src).unwrap();
+        let tc = TokenCounter::approx(4.0);
+        let ch = chunk_annotated("doc::1", src, &doc, &ChunkingConfig::default(), &tc);
+        let text: String = ch.iter().map(|c| c.text.as_str()).collect();
+        assert!(text.contains("title: draft"));
+        assert!(text.contains("Body text."));
+    }
+
+    #[test]
+    fn test_not_annotated() {
+        assert!(parse_annotated("# Plain markdown\n\nText.").is_none());
+        assert!(parse_annotated("<<<BLOCK:0>>>\nunterminated").is_none());
+        assert!(parse_annotated("<<<BLOCK:0>>>\nx\n<<<END:1>>>").is_none());
+        assert!(!looks_annotated("# Plain markdown\n\n<<<BLOCK:0>>>\n"));
+        assert!(looks_annotated("\u{FEFF}\r\n<<<BLOCK:0>>>\r\nunterminated"));
+    }
+}
@@ -0,0 +1,299 @@
+// file: src/chunking/chunker.rs
+//
+// Подключаемые стратегии чанкинга: трейт `Chunker` и встроенные реализации.
+// Стратегия выбирается по `ChunkingConfig::strategy` (`chunker_for`),
+// свои реализации можно передать в `DocumentIndexer::set_chunker`.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+
+use super::{
+    chunk_document_with_counter, chunk_id, hard_cut, locate_chunks, pack_windows, parse_blocks,
+    parse_front_matter, parse_header, semantic_chunks, sentence_spans, split_oversize, Block,
+    BlockKind, Chunk, ChunkingConfig, ChunkingStrategy, SpanPos, TokenCounter,
+};
+
+/// Эмбеддинг текста для стратегий, которым нужны векторы (semantic).
+pub type EmbedFn = Arc<dyn Fn(&str) -> Result<Vec<f32>> + Send + Sync>;
+
+/// Стратегия разбиения документа на чанки.
+pub trait Chunker: Send + Sync {
+    /// Имя стратегии (для логов).
+    fn name(&self) -> &str;
+
+    /// Чанки документа; спаны — байтовые смещения в `input`.
+    fn chunk(&self, doc_id: &str, input: &str) -> Result<Vec<Chunk>>;
+}
+
+/// Встроенный чанкер для `cfg.strategy`. Semantic требует `embed`.
+pub fn chunker_for(
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+    embed: Option<EmbedFn>,
+) -> Result<Box<dyn Chunker>> {
+    Ok(match cfg.strategy {
+        ChunkingStrategy::Blocks => Box::new(MarkdownBlockChunker::new(cfg, tc)),
+        ChunkingStrategy::SentenceWindow => Box::new(SentenceWindowChunker::new(cfg, tc)),
+        ChunkingStrategy::TokenWindow => Box::new(TokenWindowChunker::new(cfg, tc)),
+        ChunkingStrategy::Semantic => {
+            let embed = embed.ok_or_else(|| anyhow!("semantic chunker needs an embedder"))?;
+            Box::new(SemanticChunker::new(cfg, tc, embed))
+        }
+    })
+}
+
+/// Markdown-блоки (`chunk_document`): структура, заголовки, склейка мелких блоков.
+pub struct MarkdownBlockChunker {
+    cfg: ChunkingConfig,
+    tc: TokenCounter,
+}
+
+impl MarkdownBlockChunker {
+    pub fn new(cfg: &ChunkingConfig, tc: &TokenCounter) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            tc: tc.clone(),
+        }
+    }
+}
+
+impl Chunker for MarkdownBlockChunker {
+    fn name(&self) -> &str {
+        ChunkingStrategy::Blocks.as_str()
+    }
+
+    fn chunk(&self, doc_id: &str, input: &str) -> Result<Vec<Chunk>> {
+        Ok(chunk_document_with_counter(doc_id, input, &self.cfg, &self.tc))
+    }
+}
+
+/// Окна из целых предложений по всему документу (до `max_tokens`,
+/// перекрытие `overlap_tokens` целыми предложениями), границы блоков игнорируются.
+pub struct SentenceWindowChunker {
+    cfg: ChunkingConfig,
+    tc: TokenCounter,
+}
+
+impl SentenceWindowChunker {
+    pub fn new(cfg: &ChunkingConfig, tc: &TokenCounter) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            tc: tc.clone(),
+        }
+    }
+}
+
+impl Chunker for SentenceWindowChunker {
+    fn name(&self) -> &str {
+        ChunkingStrategy::SentenceWindow.as_str()
+    }
+
+    fn chunk(&self, doc_id: &str, input: &str) -> Result<Vec<Chunk>> {
+        let from = body_start(input);
+        let body = &input[from..];
+        let spans: Vec<(usize, usize)> = split_oversize(body, &sentence_spans(body), &self.cfg, &self.tc)
+            .into_iter()
+            .map(|(s, e)| (from + s, from + e))
+            .collect();
+        let windows = pack_windows(input, &spans, &self.cfg, &self.tc);
+        Ok(windows_to_chunks(doc_id, input, &windows))
+    }
+}
+
+/// Окна фиксированной длины `max_tokens` с шагом `max_tokens - overlap_tokens`
+/// (без токенайзера — по `approx_chars_per_token` символов на токен).
+pub struct TokenWindowChunker {
+    cfg: ChunkingConfig,
+    tc: TokenCounter,
+}
+
+impl TokenWindowChunker {
+    pub fn new(cfg: &ChunkingConfig, tc: &TokenCounter) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            tc: tc.clone(),
+        }
+    }
+}
+
+impl Chunker for TokenWindowChunker {
+    fn name(&self) -> &str {
+        ChunkingStrategy::TokenWindow.as_str()
+    }
+
+    fn chunk(&self, doc_id: &str, input: &str) -> Result<Vec<Chunk>> {
+        let windows = token_windows(input, body_start(input), &self.cfg, &self.tc);
+        Ok(windows_to_chunks(doc_id, input, &windows))
+    }
+}
+
+/// Семантический чанкинг (`semantic_chunks`) с переданной функцией эмбеддинга.
+pub struct SemanticChunker {
+    cfg: ChunkingConfig,
+    tc: TokenCounter,
+    embed: EmbedFn,
+}
+
+impl SemanticChunker {
+    pub fn new(cfg: &ChunkingConfig, tc: &TokenCounter, embed: EmbedFn) -> Self {
+        Self {
+            cfg: cfg.clone(),
+            tc: tc.clone(),
+            embed,
+        }
+    }
+}
+
+impl Chunker for SemanticChunker {
+    fn name(&self) -> &str {
+        ChunkingStrategy::Semantic.as_str()
+    }
+
+    fn chunk(&self, doc_id: &str, input: &str) -> Result<Vec<Chunk>> {
+        semantic_chunks(doc_id, input, &self.cfg, &self.tc, |s| (self.embed)(s))
+    }
+}
+
+/// Начало текста после front matter.
+fn body_start(input: &str) -> usize {
+    parse_front_matter(input).map_or(0, |(end, _)| end)
+}
+
+fn token_windows(input: &str, from: usize, cfg: &ChunkingConfig, tc: &TokenCounter) -> Vec<(usize, usize)> {
+    let body = &input[from..];
+    let starts: Vec<usize> = match tc.token_starts(body) {
+        Some(starts) => starts.into_iter().filter(|&p| body.is_char_boundary(p)).collect(),
+        None => {
+            let step = tc.approx_chars_per_token.round().max(1.0) as usize;
+            body.char_indices().step_by(step).map(|(p, _)| p).collect()
+        }
+    };
+    if starts.is_empty() {
+        return Vec::new();
+    }
+
+    let size = cfg.max_tokens.max(1);
+    let stride = size.saturating_sub(cfg.overlap_tokens).max(1);
+    let mut windows = Vec::new();
+    let mut i = 0;
+    loop {
+        let s = from + starts[i];
+        let e = starts.get(i + size).map_or(input.len(), |&p| from + p);
+        if e - s > cfg.hard_max_bytes {
+            hard_cut(input, s, e, cfg, tc, &mut windows);
+        } else {
+            windows.push((s, e));
+        }
+        if i + size >= starts.len() {
+            break;
+        }
+        i += stride;
+    }
+    windows
+}
+
+/// Чанки из готовых окон: виды блоков, путь заголовков на начало окна
+/// и язык, если окно целиком внутри code fence.
+fn windows_to_chunks(doc_id: &str, input: &str, windows: &[(usize, usize)]) -> Vec<Chunk> {
+    let blocks = parse_blocks(input);
+    let mut chunks = Vec::with_capacity(windows.len());
+
+    for &(start, end) in windows {
+        let text = &input[start..end];
+        if text.trim().is_empty() {
+            continue;
+        }
+        let content_start = end - text.trim_start().len();
+        let overlapping: Vec<&Block> = blocks.iter().filter(|b| b.start < end && b.end > start).collect();
+
+        let mut kind_summary: Vec<BlockKind> = Vec::new();
+        for b in &overlapping {
+            if !kind_summary.contains(&b.kind) {
+                kind_summary.push(b.kind);
+            }
+        }
+        if kind_summary.is_empty() {
+            kind_summary.push(BlockKind::Paragraph);
+        }
+
+        let mut heading_stack: Vec<(usize, String)> = Vec::new();
+        for b in blocks.iter().filter(|b| b.kind == BlockKind::Header && b.start <= content_start) {
+            if let Some((level, title)) = parse_header(&input[b.start..b.end]) {
+                heading_stack.retain(|(l, _)| *l < level);
+                heading_stack.push((level, title));
+            }
+        }
+
+        let lang = match overlapping.as_slice() {
+            [b] if b.kind == BlockKind::CodeFence => b.lang.clone(),
+            _ => None,
+        };
+
+        chunks.push(Chunk {
+            id: chunk_id(doc_id, start, end, text),
+            kind_summary,
+            start,
+            end,
+            text: text.to_string(),
+            headings: heading_stack.into_iter().map(|(_, t)| t).collect(),
+            lang,
+            pos: SpanPos::default(),
+        });
+    }
+
+    locate_chunks(input, &mut chunks);
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cfg(strategy: ChunkingStrategy) -> ChunkingConfig {
+        ChunkingConfig {
+            max_tokens: 20,
+            overlap_tokens: 5,
+            strategy,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_strategy_names() {
+        for s in ["blocks", "markdown-block", "sentence_window", "token-window", "semantic"] {
+            let strategy: ChunkingStrategy = s.parse().unwrap();
+            assert_eq!(strategy.to_string().parse::<ChunkingStrategy>().unwrap(), strategy);
+        }
+        assert!("paragraphs".parse::<ChunkingStrategy>().is_err());
+        let tc = TokenCounter::approx(4.0);
+        assert!(chunker_for(&cfg(ChunkingStrategy::Semantic), &tc, None).is_err());
+    }
+
+    #[test]
+    fn test_sentence_window_ignores_blocks() {
+        let doc = "# Intro\n\nFirst sentence is here. Second one.\n\nThird sentence in another paragraph. \
+                   Fourth sentence closes the text.";
+        let cfg = cfg(ChunkingStrategy::SentenceWindow);
+        let tc = TokenCounter::approx(cfg.approx_chars_per_token);
+        let ch = chunker_for(&cfg, &tc, None).unwrap().chunk("doc::1", doc).unwrap();
+        assert!(ch.len() > 1);
+        // Windows cross paragraph boundaries and keep the heading path
+        assert!(ch.iter().any(|c| c.text.contains("Second one.\n\nThird")));
+        assert!(ch.iter().all(|c| c.headings == vec!["Intro"]));
+        assert!(ch.iter().all(|c| tc.count(&c.text) <= cfg.max_tokens));
+    }
+
+    #[test]
+    fn test_token_window_overlap() {
+        let doc = "abcdefghij".repeat(20);
+        let cfg = cfg(ChunkingStrategy::TokenWindow);
+        let tc = TokenCounter::approx(cfg.approx_chars_per_token);
+        let ch = TokenWindowChunker::new(&cfg, &tc).chunk("doc::1", &doc).unwrap();
+        // 20 tokens * 4 chars per window, stride 15 tokens = 60 chars
+        assert_eq!(ch[0].text.len(), 80);
+        assert_eq!(ch[1].start, 60);
+        assert_eq!(ch.last().unwrap().end, doc.len());
+        assert_eq!(ch.len(), 3);
+    }
+}
@@ -0,0 +1,1471 @@
+// file: src/chunking/mod.rs
+
+use regex::Regex;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use tokenizers::Tokenizer;
+
+mod annotated;
+mod chunker;
+mod position;
+mod semantic;
+mod sentences;
+mod stream;
+
+pub use annotated::{chunk_annotated, export_blocks, export_chunks, looks_annotated, parse_annotated, AnnotatedDocument};
+pub use chunker::{
+    chunker_for, Chunker, EmbedFn, MarkdownBlockChunker, SemanticChunker, SentenceWindowChunker,
+    TokenWindowChunker,
+};
+pub use position::{locate_chunks, SpanPos};
+pub use semantic::semantic_chunks;
+pub use sentences::{sentence_spans, split_sentences};
+pub use stream::StreamingChunker;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockKind {
+    Header,
+    CodeFence,
+    List,
+    Table,
+    Quote,
+    Paragraph,
+    Hr,
+    Blank,
+}
+
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub kind: BlockKind,
+    pub start: usize,
+    pub end: usize,
+    pub lang: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    pub id: String,
+    pub kind_summary: Vec<BlockKind>,
+    pub start: usize,
+    pub end: usize,
+    /// Обычно `input[start..end]`; у продолжений таблицы впереди повторён её заголовок.
+    pub text: String,
+    /// Путь заголовков секции, например `["Section 2", "Subsection 2.1"]`.
+    pub headings: Vec<String>,
+    /// Язык code fence (```rust → `rust`).
+    pub lang: Option<String>,
+    /// Строки/колонки и символьные смещения `start..end`.
+    pub pos: SpanPos,
+}
+
+/// Метаданные документа (front matter и т.п.), попадают в payload каждого чанка.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocumentMeta {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub tags: Vec<String>,
+    /// Остальные поля как есть.
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl DocumentMeta {
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none() && self.author.is_none() && self.tags.is_empty() && self.extra.is_empty()
+    }
+
+    /// Дополняет незаполненные поля значениями из `other`.
+    pub fn merge(&mut self, other: DocumentMeta) {
+        if self.title.is_none() {
+            self.title = other.title;
+        }
+        if self.author.is_none() {
+            self.author = other.author;
+        }
+        if self.tags.is_empty() {
+            self.tags = other.tags;
+        }
+        for (k, v) in other.extra {
+            self.extra.entry(k).or_insert(v);
+        }
+    }
+}
+
+/// Стратегия разбиения документа на чанки (встроенные реализации `Chunker`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChunkingStrategy {
+    /// Markdown-блоки (`parse_blocks`), большие блоки режутся по предложениям.
+    #[default]
+    #[serde(alias = "markdown", alias = "markdown-block")]
+    Blocks,
+    /// Окна из целых предложений без учёта markdown-структуры.
+    SentenceWindow,
+    /// Окна фиксированной длины в токенах с перекрытием.
+    TokenWindow,
+    /// Разрывы по падению сходства эмбеддингов соседних предложений.
+    Semantic,
+}
+
+impl ChunkingStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Blocks => "blocks",
+            Self::SentenceWindow => "sentence-window",
+            Self::TokenWindow => "token-window",
+            Self::Semantic => "semantic",
+        }
+    }
+}
+
+impl std::fmt::Display for ChunkingStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ChunkingStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "blocks" | "markdown" | "markdown-block" => Ok(Self::Blocks),
+            "sentence-window" | "sentences" => Ok(Self::SentenceWindow),
+            "token-window" | "tokens" | "fixed" => Ok(Self::TokenWindow),
+            "semantic" => Ok(Self::Semantic),
+            other => Err(format!(
+                "unknown chunker `{}` (expected blocks|sentence-window|token-window|semantic)",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChunkingConfig {
+    pub max_tokens: usize,
+    pub overlap_tokens: usize,
+    /// Переносить хвост предыдущего текстового блока в начало следующего.
+    pub overlap_across_blocks: bool,
+    pub approx_chars_per_token: f32,
+    pub hard_max_bytes: usize,
+    /// Добавлять путь заголовков к тексту перед эмбеддингом.
+    pub embed_headings: bool,
+    pub strategy: ChunkingStrategy,
+    /// Semantic: перцентиль расстояний между соседями, выше которого ставится разрыв.
+    pub semantic_breakpoint_percentile: f32,
+    /// Semantic: сколько соседних предложений с каждой стороны эмбеддить вместе.
+    pub semantic_buffer_size: usize,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            max_tokens: 400,
+            overlap_tokens: 60,
+            overlap_across_blocks: false,
+            approx_chars_per_token: 4.0,
+            hard_max_bytes: 64 * 1024,
+            embed_headings: true,
+            strategy: ChunkingStrategy::Blocks,
+            semantic_breakpoint_percentile: 95.0,
+            semantic_buffer_size: 1,
+        }
+    }
+}
+
+fn approx_tokens(s: &str, cpt: f32) -> usize {
+    ((s.chars().count() as f32) / cpt).ceil() as usize
+}
+
+/// Подсчёт токенов для чанкинга: токенайзер модели эмбеддингов,
+/// а при его отсутствии или ошибке — приближение по числу символов.
+#[derive(Clone)]
+pub struct TokenCounter {
+    tokenizer: Option<Tokenizer>,
+    approx_chars_per_token: f32,
+}
+
+impl TokenCounter {
+    /// Только приближение `chars / approx_chars_per_token`.
+    pub fn approx(approx_chars_per_token: f32) -> Self {
+        Self {
+            tokenizer: None,
+            approx_chars_per_token,
+        }
+    }
+
+    /// Реальный токенайзер (копия без truncation/padding, чтобы не искажать счёт).
+    pub fn with_tokenizer(tokenizer: &Tokenizer, approx_chars_per_token: f32) -> Self {
+        let mut tokenizer = tokenizer.clone();
+        let _ = tokenizer.with_truncation(None);
+        tokenizer.with_padding(None);
+        Self {
+            tokenizer: Some(tokenizer),
+            approx_chars_per_token,
+        }
+    }
+
+    pub fn count(&self, s: &str) -> usize {
+        self.tokenizer
+            .as_ref()
+            .and_then(|t| t.encode(s, false).ok())
+            .map(|enc| enc.len())
+            .unwrap_or_else(|| approx_tokens(s, self.approx_chars_per_token))
+    }
+
+    /// Байтовые смещения начала каждого токена (только с токенайзером).
+    fn token_starts(&self, s: &str) -> Option<Vec<usize>> {
+        let enc = self.tokenizer.as_ref()?.encode(s, false).ok()?;
+        Some(enc.get_offsets().iter().map(|&(start, _)| start).collect())
+    }
+}
+
+lazy_static! {
+    static ref RE_HEADER: Regex = Regex::new(r"(?m)^(?P<hash>#{1,6})\s+(.+?)\s*$").unwrap();
+    static ref RE_CODE_FENCE_OPEN: Regex = Regex::new(r"(?m)^(`{3,})([A-Za-z0-9_+-]+)?\s*$").unwrap();
+    static ref RE_CODE_FENCE_CLOSE: Regex = Regex::new(r"(?m)^(`{3,})\s*$").unwrap();
+    static ref RE_LIST: Regex = Regex::new(r#"(?m)^(?:\s{0,3}(?:[-+*]|\d{1,3}[.)]))\s+"#).unwrap();
+    static ref RE_TABLE_ROW: Regex = Regex::new(r"(?m)^\s*\|.+\|\s*$").unwrap();
+    static ref RE_TABLE_SEP: Regex = Regex::new(r"(?m)^\s*\|\s*:?-+:?\s*(\|\s*:?-+:?\s*)+\s*\|?\s*$").unwrap();
+    static ref RE_QUOTE: Regex = Regex::new(r"(?m)^\s*>\s+").unwrap();
+    static ref RE_HR: Regex = Regex::new(r"(?m)^\s*(?:[-]{3,}|[_]{3,}|[*]{3,})\s*$").unwrap();
+    static ref RE_BLANK: Regex = Regex::new(r"(?m)^\s*$").unwrap();
+
+    // Начала верхнеуровневых элементов кода (строка без отступа)
+    static ref RE_ITEM_RUST: Regex = Regex::new(
+        r"^(?:pub(?:\([^)]*\))?\s+)?(?:(?:async|const|unsafe|extern(?:\s+\S+)?)\s+)*(?:fn|struct|enum|union|impl|trait|mod|type|static|const|macro_rules!)\b"
+    ).unwrap();
+    static ref RE_ITEM_PYTHON: Regex = Regex::new(r"^(?:async\s+def|def|class)\b").unwrap();
+    static ref RE_ITEM_JS: Regex = Regex::new(
+        r"^(?:export\s+(?:default\s+)?)?(?:async\s+)?(?:function\*?|class|const|let|var|interface|type|enum)\b"
+    ).unwrap();
+    static ref RE_ITEM_GO: Regex = Regex::new(r"^(?:func|type|var|const)\b").unwrap();
+    static ref RE_ITEM_BRACE: Regex = Regex::new(r"^[A-Za-z_][^;{}]*(?:\(|\bclass\b|\bstruct\b|\binterface\b|\bnamespace\b)").unwrap();
+}
+
+/// Конец блока: `i` указывает за последнюю строку блока, завершающий `\n` не входит в блок.
+fn block_end(bytes: &[u8], start: usize, i: usize) -> usize {
+    if i > start && bytes[i - 1] == b'\n' {
+        i - 1
+    } else {
+        i
+    }
+}
+
+/// YAML (`---`) или TOML (`+++`) front matter в начале документа:
+/// байтовое смещение конца блока и разобранные поля.
+pub fn parse_front_matter(input: &str) -> Option<(usize, DocumentMeta)> {
+    let body_start = input.len() - input.trim_start_matches('\u{FEFF}').len();
+    let rest = &input[body_start..];
+    let delim = if rest.starts_with("---") {
+        "---"
+    } else if rest.starts_with("+++") {
+        "+++"
+    } else {
+        return None;
+    };
+    let first_nl = rest.find('\n')?;
+    if rest[..first_nl].trim_end() != delim {
+        return None;
+    }
+
+    // Closing delimiter on its own line
+    let mut pos = first_nl + 1;
+    for line in rest[first_nl + 1..].split_inclusive('\n') {
+        if line.trim_end() == delim {
+            let raw = &rest[first_nl + 1..pos];
+            let value: serde_json::Value = if delim == "---" {
+                serde_yaml::from_str(raw).ok()?
+            } else {
+                toml::from_str(raw).ok()?
+            };
+            let meta = meta_from_fields(value.as_object()?);
+            return Some((body_start + pos + line.len(), meta));
+        }
+        pos += line.len();
+    }
+    None
+}
+
+pub(crate) fn meta_from_fields(fields: &serde_json::Map<String, serde_json::Value>) -> DocumentMeta {
+    let mut meta = DocumentMeta::default();
+    for (key, value) in fields {
+        match key.to_ascii_lowercase().as_str() {
+            "title" => meta.title = value_to_string(value),
+            "author" | "authors" => meta.author = value_to_string(value),
+            "tags" | "keywords" => meta.tags = value_to_list(value),
+            _ => {
+                meta.extra.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    meta
+}
+
+pub(crate) fn value_to_string(v: &serde_json::Value) -> Option<String> {
+    match v {
+        serde_json::Value::String(s) => Some(s.trim().to_string()),
+        serde_json::Value::Array(items) => {
+            let parts: Vec<String> = items.iter().filter_map(value_to_string).collect();
+            (!parts.is_empty()).then(|| parts.join(", "))
+        }
+        serde_json::Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+fn value_to_list(v: &serde_json::Value) -> Vec<String> {
+    match v {
+        serde_json::Value::Array(items) => items.iter().filter_map(value_to_string).collect(),
+        serde_json::Value::String(s) => s
+            .split(',')
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Закрывает ли строка code fence, открытый `open` backtick-ами: забор
+/// покороче — часть кода (так в блок можно положить код с ```).
+fn closes_fence(line: &str, open: usize) -> bool {
+    RE_CODE_FENCE_CLOSE.captures(line).is_some_and(|c| c[1].len() >= open)
+}
+
+pub fn parse_blocks(input: &str) -> Vec<Block> {
+    // Front matter is metadata, not content
+    parse_blocks_from(input, parse_front_matter(input).map_or(0, |(end, _)| end))
+}
+
+/// Пропускает тело code fence, открытого `open` backtick-ами, с байта `i`:
+/// позиция за закрывающей строкой или конец текста, если забор не закрыт.
+fn skip_fence_body(input: &str, mut i: usize, open: usize) -> usize {
+    let bytes = input.as_bytes();
+    let len = bytes.len();
+    while i < len {
+        let mut line_end = i;
+        while line_end < len && bytes[line_end] != b'\n' {
+            line_end += 1;
+        }
+        let closed = closes_fence(&input[i..line_end], open);
+        i = if line_end < len { line_end + 1 } else { line_end };
+        if closed {
+            break;
+        }
+    }
+    i
+}
+
+/// Блоки начиная с байта `i` (без поиска front matter — для кусков потока).
+fn parse_blocks_from(input: &str, mut i: usize) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let bytes = input.as_bytes();
+    let len = bytes.len();
+
+    while i < len {
+        // Find line boundaries
+        let line_start = i;
+        let mut line_end = i;
+        while line_end < len && bytes[line_end] != b'\n' {
+            line_end += 1;
+        }
+        
+        // Get the line as &str (safe because we're working with line boundaries)
+        let line = if line_end <= len {
+            &input[line_start..line_end]
+        } else {
+            &input[line_start..]
+        };
+
+        // Code fence
+        if let Some(caps) = RE_CODE_FENCE_OPEN.captures(line) {
+            let fence_start = line_start;
+            let lang = caps.get(2).map(|m| m.as_str().to_ascii_lowercase());
+            let open = caps[1].len();
+            
+            // Skip opening fence line
+            i = if line_end < len { line_end + 1 } else { line_end };
+            i = skip_fence_body(input, i, open);
+            
+            blocks.push(Block {
+                kind: BlockKind::CodeFence,
+                start: fence_start,
+                end: block_end(bytes, fence_start, i),
+                lang,
+            });
+            continue;
+        }
+
+        // Table - group consecutive table rows
+        if RE_TABLE_ROW.is_match(line) || RE_TABLE_SEP.is_match(line) {
+            let table_start = line_start;
+            
+            while i < len {
+                let t_line_start = i;
+                let mut t_line_end = i;
+                while t_line_end < len && bytes[t_line_end] != b'\n' {
+                    t_line_end += 1;
+                }
+                
+                let t_line = &input[t_line_start..t_line_end];
+                if !RE_TABLE_ROW.is_match(t_line) && !RE_TABLE_SEP.is_match(t_line) {
+                    break;
+                }
+                
+                i = if t_line_end < len { t_line_end + 1 } else { t_line_end };
+            }
+            
+            blocks.push(Block {
+                kind: BlockKind::Table,
+                start: table_start,
+                end: block_end(bytes, table_start, i),
+                lang: None,
+            });
+            continue;
+        }
+
+        // List - group consecutive list items
+        if RE_LIST.is_match(line) {
+            let list_start = line_start;
+            
+            while i < len {
+                let l_line_start = i;
+                let mut l_line_end = i;
+                while l_line_end < len && bytes[l_line_end] != b'\n' {
+                    l_line_end += 1;
+                }
+                
+                let l_line = &input[l_line_start..l_line_end];
+                // Continue if list item or indented continuation
+                if RE_LIST.is_match(l_line) || (l_line.starts_with("  ") && !l_line.trim().is_empty()) {
+                    i = if l_line_end < len { l_line_end + 1 } else { l_line_end };
+                } else {
+                    break;
+                }
+            }
+            
+            blocks.push(Block {
+                kind: BlockKind::List,
+                start: list_start,
+                end: block_end(bytes, list_start, i),
+                lang: None,
+            });
+            continue;
+        }
+
+        // Quote - group consecutive quote lines
+        if RE_QUOTE.is_match(line) {
+            let quote_start = line_start;
+            
+            while i < len {
+                let q_line_start = i;
+                let mut q_line_end = i;
+                while q_line_end < len && bytes[q_line_end] != b'\n' {
+                    q_line_end += 1;
+                }
+                
+                let q_line = &input[q_line_start..q_line_end];
+                if !RE_QUOTE.is_match(q_line) {
+                    break;
+                }
+                
+                i = if q_line_end < len { q_line_end + 1 } else { q_line_end };
+            }
+            
+            blocks.push(Block {
+                kind: BlockKind::Quote,
+                start: quote_start,
+                end: block_end(bytes, quote_start, i),
+                lang: None,
+            });
+            continue;
+        }
+
+        // Header
+        if RE_HEADER.is_match(line) {
+            blocks.push(Block {
+                kind: BlockKind::Header,
+                start: line_start,
+                end: line_end,
+                lang: None,
+            });
+            i = if line_end < len { line_end + 1 } else { line_end };
+            continue;
+        }
+
+        // HR - skip
+        if RE_HR.is_match(line) {
+            i = if line_end < len { line_end + 1 } else { line_end };
+            continue;
+        }
+
+        // Blank line - skip
+        if line.trim().is_empty() {
+            i = if line_end < len { line_end + 1 } else { line_end };
+            continue;
+        }
+
+        // Paragraph - group consecutive non-blank lines
+        if !line.trim().is_empty() {
+            let para_start = line_start;
+            
+            while i < len {
+                let p_line_start = i;
+                let mut p_line_end = i;
+                while p_line_end < len && bytes[p_line_end] != b'\n' {
+                    p_line_end += 1;
+                }
+                
+                let p_line = &input[p_line_start..p_line_end];
+                
+                // Stop at special blocks or blank lines
+                if p_line.trim().is_empty()
+                    || RE_HEADER.is_match(p_line)
+                    || RE_CODE_FENCE_OPEN.is_match(p_line)
+                    || RE_CODE_FENCE_CLOSE.is_match(p_line)
+                    || RE_LIST.is_match(p_line)
+                    || RE_TABLE_ROW.is_match(p_line)
+                    || RE_QUOTE.is_match(p_line)
+                    || RE_HR.is_match(p_line)
+                {
+                    break;
+                }
+                
+                i = if p_line_end < len { p_line_end + 1 } else { p_line_end };
+            }
+            
+            blocks.push(Block {
+                kind: BlockKind::Paragraph,
+                start: para_start,
+                end: block_end(bytes, para_start, i),
+                lang: None,
+            });
+            continue;
+        }
+
+        i = if line_end < len { line_end + 1 } else { line_end };
+    }
+
+    blocks
+}
+
+/// Размер (в символах), ниже которого блок считается «мелким» и приклеивается к соседям.
+const MIN_BLOCK_CHARS: usize = 30;
+
+/// Чанки из готовых блоков; токены оцениваются по `approx_chars_per_token`.
+pub fn make_chunks(doc_id: &str, input: &str, blocks: &[Block], cfg: &ChunkingConfig) -> Vec<Chunk> {
+    make_chunks_with(doc_id, input, blocks, cfg, &TokenCounter::approx(cfg.approx_chars_per_token))
+}
+
+/// `make_chunks` с заданным счётчиком токенов (например, токенайзером модели).
+pub fn make_chunks_with(
+    doc_id: &str,
+    input: &str,
+    blocks: &[Block],
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+) -> Vec<Chunk> {
+    let mut chunks = make_chunks_with_headings(doc_id, input, blocks, cfg, tc, &mut Vec::new());
+    locate_chunks(input, &mut chunks);
+    chunks
+}
+
+/// `make_chunks` с внешним стеком заголовков: потоковый чанкер переносит
+/// хлебные крошки между кусками файла.
+fn make_chunks_with_headings(
+    doc_id: &str,
+    input: &str,
+    blocks: &[Block],
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+    heading_stack: &mut Vec<(usize, String)>,
+) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut prev_last: Option<&Block> = None;
+    let mut segment: Vec<&Block> = Vec::new();
+
+    for b in blocks {
+        let text = &input[b.start..b.end];
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        if !segment.is_empty() && !joins_segment(input, &segment, b, cfg, tc) {
+            emit_segment(doc_id, input, &segment, prev_last, heading_stack, cfg, tc, &mut chunks);
+            prev_last = segment.last().copied();
+            segment.clear();
+        }
+
+        if b.kind == BlockKind::Header {
+            if let Some((level, title)) = parse_header(text) {
+                heading_stack.retain(|(l, _)| *l < level);
+                heading_stack.push((level, title));
+            }
+        }
+        segment.push(b);
+    }
+    if !segment.is_empty() {
+        emit_segment(doc_id, input, &segment, prev_last, heading_stack, cfg, tc, &mut chunks);
+    }
+
+    chunks
+}
+
+/// Строки таблицы: конец заголовка (строка заголовка + разделитель, если он есть)
+/// и диапазоны строк данных.
+fn table_row_spans(text: &str) -> (Option<usize>, Vec<(usize, usize)>) {
+    let mut lines: Vec<(usize, usize)> = Vec::new();
+    let mut pos = 0;
+    for line in text.split_inclusive('\n') {
+        lines.push((pos, pos + line.trim_end_matches('\n').len()));
+        pos += line.len();
+    }
+    let has_header = lines.len() > 2 && RE_TABLE_SEP.is_match(&text[lines[1].0..lines[1].1]);
+    if has_header {
+        (Some(lines[1].1), lines[2..].to_vec())
+    } else {
+        (None, lines)
+    }
+}
+
+/// Элементы списка верхнего уровня; вложенные пункты и продолжения строк
+/// остаются с родительским элементом.
+fn list_item_spans(text: &str) -> Vec<(usize, usize)> {
+    let indent = |l: &str| l.len() - l.trim_start().len();
+    let base = indent(text);
+    let mut items: Vec<(usize, usize)> = Vec::new();
+    let mut pos = 0;
+    for line in text.split_inclusive('\n') {
+        let top_level = RE_LIST.is_match(line) && indent(line) <= base;
+        let end = pos + line.trim_end_matches('\n').len();
+        match items.last_mut() {
+            Some(last) if !top_level => last.1 = end,
+            _ => items.push((pos, end)),
+        }
+        pos += line.len();
+    }
+    items
+}
+
+fn is_small(text: &str) -> bool {
+    text.trim().chars().count() < MIN_BLOCK_CHARS
+}
+
+/// Можно ли приклеить блок `b` к текущему сегменту: заголовок начинает новый
+/// сегмент (если в сегменте не одни заголовки), мелкие блоки склеиваются
+/// с соседями, пока сегмент влезает в `max_tokens`/`hard_max_bytes`.
+/// Заголовки и мелкие сегменты приклеиваются и к большому блоку.
+fn joins_segment(input: &str, segment: &[&Block], b: &Block, cfg: &ChunkingConfig, tc: &TokenCounter) -> bool {
+    let only_headers = segment.iter().all(|s| s.kind == BlockKind::Header);
+    if b.kind == BlockKind::Header && !only_headers {
+        return false;
+    }
+    let seg_start = segment[0].start;
+    let seg_text = &input[seg_start..segment[segment.len() - 1].end];
+    let block_text = &input[b.start..b.end];
+    // Headers always lead into the following content
+    let leading = only_headers || is_small(seg_text);
+    if !leading && !is_small(block_text) {
+        return false;
+    }
+    fits(&input[seg_start..b.end], cfg, tc) || (leading && !fits(block_text, cfg, tc))
+}
+
+/// Нарезает сегмент из подряд идущих блоков на окна и добавляет чанки.
+#[allow(clippy::too_many_arguments)]
+fn emit_segment(
+    doc_id: &str,
+    input: &str,
+    segment: &[&Block],
+    prev_last: Option<&Block>,
+    heading_stack: &[(usize, String)],
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+    chunks: &mut Vec<Chunk>,
+) {
+    let first = segment[0];
+    let seg_start = first.start;
+    let seg_end = segment[segment.len() - 1].end;
+
+    // Table headers (header row + separator) repeated atop continuation chunks
+    let mut table_headers: Vec<(usize, usize, usize)> = Vec::new();
+
+    let windows = if fits(&input[seg_start..seg_end], cfg, tc) {
+        // Segment fits, create single chunk
+        vec![(seg_start, seg_end)]
+    } else {
+        // Too large: code by structure, tables by rows, lists by items, prose by sentences
+        let mut spans = Vec::new();
+        for b in segment {
+            let text = &input[b.start..b.end];
+            if fits(text, cfg, tc) {
+                spans.push((b.start, b.end));
+                continue;
+            }
+            let block_spans = match b.kind {
+                BlockKind::CodeFence => code_spans(text, b.lang.as_deref(), cfg, tc),
+                BlockKind::Table => {
+                    let (header_end, rows) = table_row_spans(text);
+                    // Rows leave room for the header repeated above them
+                    let row_cfg = match header_end {
+                        Some(he) => {
+                            table_headers.push((b.start, b.start + he, b.end));
+                            reserve_cfg(cfg, header_reserve(&text[..he], tc))
+                        }
+                        None => cfg.clone(),
+                    };
+                    let mut units = Vec::new();
+                    for (s, e) in rows {
+                        if fits(&text[s..e], &row_cfg, tc) {
+                            units.push((s, e));
+                        } else {
+                            split_table_row(text, s, e, &row_cfg, tc, &mut units);
+                        }
+                    }
+                    units
+                }
+                BlockKind::List => {
+                    let mut items = Vec::new();
+                    for (s, e) in list_item_spans(text) {
+                        if fits(&text[s..e], cfg, tc) {
+                            items.push((s, e));
+                        } else {
+                            let item = &text[s..e];
+                            let sents = sentence_spans(item).into_iter().map(|(a, z)| (s + a, s + z));
+                            items.extend(split_oversize(text, &sents.collect::<Vec<_>>(), cfg, tc));
+                        }
+                    }
+                    items
+                }
+                _ => split_oversize(text, &sentence_spans(text), cfg, tc),
+            };
+            spans.extend(block_spans.into_iter().map(|(s, e)| (b.start + s, b.start + e)));
+        }
+
+        // Windows starting inside a table leave room for its repeated header
+        let reserve: Vec<(usize, usize)> = spans
+            .iter()
+            .map(|&(s, _)| {
+                table_headers
+                    .iter()
+                    .find(|&&(_, he, te)| s > he && s < te)
+                    .map_or((0, 0), |&(hs, he, _)| header_reserve(&input[hs..he], tc))
+            })
+            .collect();
+        pack_windows_reserving(input, &spans, &reserve, cfg, tc)
+    };
+
+    let mut kinds: Vec<BlockKind> = Vec::new();
+    for b in segment {
+        if !kinds.contains(&b.kind) {
+            kinds.push(b.kind);
+        }
+    }
+    let headings: Vec<String> = heading_stack.iter().map(|(_, t)| t.clone()).collect();
+
+    for (wi, (ws, we)) in windows.into_iter().enumerate() {
+        let mut chunk_start = ws;
+        let chunk_end = we;
+        // Kinds of the blocks this window touches
+        let mut kind_summary: Vec<BlockKind> = Vec::new();
+        for b in segment.iter().filter(|b| b.start < we && b.end > ws) {
+            if !kind_summary.contains(&b.kind) {
+                kind_summary.push(b.kind);
+            }
+        }
+        if kind_summary.is_empty() {
+            kind_summary = kinds.clone();
+        }
+
+        // Optionally carry the tail of the previous block into the first window
+        if wi == 0 && cfg.overlap_across_blocks {
+            if let Some(prev) = prev_last.filter(|p| is_prose(p.kind) && is_prose(first.kind)) {
+                let used = tc.count(&input[chunk_start..chunk_end]);
+                let budget = cfg.overlap_tokens.min(cfg.max_tokens.saturating_sub(used));
+                let prev_text = &input[prev.start..prev.end];
+                if let Some(off) = tail_overlap_start(prev_text, budget, tc) {
+                    if chunk_end - (prev.start + off) <= cfg.hard_max_bytes {
+                        chunk_start = prev.start + off;
+                        if !kind_summary.contains(&prev.kind) {
+                            kind_summary.insert(0, prev.kind);
+                        }
+                    }
+                }
+            }
+        }
+
+        let chunk_text = &input[chunk_start..chunk_end];
+        if chunk_text.trim().is_empty() {
+            continue;
+        }
+        let text = match table_headers
+            .iter()
+            .find(|&&(_, he, te)| chunk_start > he && chunk_start < te)
+        {
+            Some(&(hs, he, _)) => format!("{}\n{}", &input[hs..he], chunk_text),
+            None => chunk_text.to_string(),
+        };
+        let id = chunk_id(doc_id, chunk_start, chunk_end, &text);
+        chunks.push(Chunk {
+            id,
+            kind_summary,
+            start: chunk_start,
+            end: chunk_end,
+            text,
+            headings: headings.clone(),
+            lang: segment.iter().find_map(|b| b.lang.clone()),
+            pos: SpanPos::default(),
+        });
+    }
+}
+
+/// Уровень (число `#`) и текст заголовка.
+fn parse_header(line: &str) -> Option<(usize, String)> {
+    let caps = RE_HEADER.captures(line)?;
+    let level = caps.name("hash")?.as_str().len();
+    let title = caps.get(2)?.as_str().trim_end_matches('#').trim();
+    Some((level, title.to_string()))
+}
+
+/// Текст для эмбеддинга: путь заголовков (если включено) + текст чанка.
+pub fn passage_text(chunk: &Chunk, cfg: &ChunkingConfig) -> String {
+    if cfg.embed_headings && !chunk.headings.is_empty() {
+        format!("{}\n\n{}", chunk.headings.join(" > "), chunk.text)
+    } else {
+        chunk.text.clone()
+    }
+}
+
+/// Место под повторённый заголовок таблицы: токены и байты (с переводом строки).
+fn header_reserve(header: &str, tc: &TokenCounter) -> (usize, usize) {
+    (tc.count(header), header.len() + 1)
+}
+
+/// Лимиты `cfg`, уменьшенные на `(токены, байты)`.
+fn reserve_cfg(cfg: &ChunkingConfig, (tokens, bytes): (usize, usize)) -> ChunkingConfig {
+    ChunkingConfig {
+        max_tokens: cfg.max_tokens.saturating_sub(tokens).max(1),
+        hard_max_bytes: cfg.hard_max_bytes.saturating_sub(bytes).max(4),
+        ..cfg.clone()
+    }
+}
+
+/// Дробит строку таблицы, не влезающую в лимиты, по границам ячеек: каждый
+/// кусок — сам строка таблицы `| a | b |`, соседние делят разделяющий `|`.
+/// Ячейка, не влезающая целиком, режется как обычный текст.
+fn split_table_row(
+    text: &str,
+    s: usize,
+    e: usize,
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+    out: &mut Vec<(usize, usize)>,
+) {
+    let row = &text.as_bytes()[s..e];
+    let pipes: Vec<usize> = (0..row.len())
+        .filter(|&i| row[i] == b'|' && (i == 0 || row[i - 1] != b'\\'))
+        .map(|i| s + i)
+        .collect();
+    if pipes.len() < 2 {
+        split_span(text, s, e, 0, cfg, tc, out);
+        return;
+    }
+    let mut a = 0;
+    while a + 1 < pipes.len() {
+        let mut b = a + 1;
+        while b + 1 < pipes.len() && fits(&text[pipes[a]..=pipes[b + 1]], cfg, tc) {
+            b += 1;
+        }
+        split_span(text, pipes[a], pipes[b] + 1, 0, cfg, tc, out);
+        a = b;
+    }
+}
+
+fn is_prose(kind: BlockKind) -> bool {
+    matches!(kind, BlockKind::Paragraph | BlockKind::List | BlockKind::Quote)
+}
+
+/// Упаковывает предложения в окна до `max_tokens`; соседние окна делят
+/// хвостовые предложения общим объёмом до `overlap_tokens`.
+/// Возвращает байтовые диапазоны относительно `text`.
+fn pack_windows(
+    text: &str,
+    spans: &[(usize, usize)],
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+) -> Vec<(usize, usize)> {
+    pack_windows_reserving(text, spans, &[], cfg, tc)
+}
+
+/// `pack_windows`, где окно, начинающееся с `spans[i]`, оставляет место
+/// `reserve[i]` (токены, байты) — под повторённый заголовок таблицы.
+fn pack_windows_reserving(
+    text: &str,
+    spans: &[(usize, usize)],
+    reserve: &[(usize, usize)],
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+) -> Vec<(usize, usize)> {
+    let budget = |i: usize| {
+        let (tokens, bytes) = reserve.get(i).copied().unwrap_or((0, 0));
+        (cfg.max_tokens.saturating_sub(tokens).max(1), cfg.hard_max_bytes.saturating_sub(bytes).max(4))
+    };
+    let toks: Vec<usize> = spans
+        .iter()
+        .map(|&(s, e)| tc.count(&text[s..e]))
+        .collect();
+    let n = spans.len();
+    let mut out = Vec::new();
+    let mut i = 0;
+
+    while i < n {
+        // Greedily extend the window (always at least one sentence)
+        let (max_tokens, max_bytes) = budget(i);
+        let mut j = i;
+        let mut sum = 0;
+        while j < n
+            && (j == i || (sum + toks[j] <= max_tokens && spans[j].1 - spans[i].0 <= max_bytes))
+        {
+            sum += toks[j];
+            j += 1;
+        }
+        out.push((spans[i].0, spans[j - 1].1));
+        if j >= n {
+            break;
+        }
+
+        // Step back over trailing sentences to seed the next window, leaving
+        // room for at least one new sentence so the window always advances
+        let mut k = j;
+        let mut ov = 0;
+        while k > i + 1
+            && ov + toks[k - 1] <= cfg.overlap_tokens
+            && ov + toks[k - 1] + toks[j] <= budget(k - 1).0
+            && spans[j].1 - spans[k - 1].0 <= budget(k - 1).1
+        {
+            ov += toks[k - 1];
+            k -= 1;
+        }
+        i = k;
+    }
+
+    out
+}
+
+fn fits(s: &str, cfg: &ChunkingConfig, tc: &TokenCounter) -> bool {
+    s.len() <= cfg.hard_max_bytes && tc.count(s) <= cfg.max_tokens
+}
+
+/// Дробит предложения, не влезающие в `max_tokens`/`hard_max_bytes`:
+/// сначала по знакам препинания внутри предложения, затем по пробелам,
+/// и в крайнем случае — по границам UTF-8 символов.
+fn split_oversize(
+    text: &str,
+    spans: &[(usize, usize)],
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+) -> Vec<(usize, usize)> {
+    let mut out = Vec::with_capacity(spans.len());
+    for &(s, e) in spans {
+        split_span(text, s, e, 0, cfg, tc, &mut out);
+    }
+    out
+}
+
+fn split_span(
+    text: &str,
+    s: usize,
+    e: usize,
+    level: u8,
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+    out: &mut Vec<(usize, usize)>,
+) {
+    if fits(&text[s..e], cfg, tc) {
+        out.push((s, e));
+        return;
+    }
+    let pieces = match level {
+        0 => cut_after(text, s, e, |c, next| {
+            matches!(c, ',' | ';' | ':' | '—' | '–') && next.is_some_and(char::is_whitespace)
+        }),
+        1 => cut_after(text, s, e, |c, next| {
+            c.is_whitespace() && next.is_some_and(|n| !n.is_whitespace())
+        }),
+        _ => {
+            hard_cut(text, s, e, cfg, tc, out);
+            return;
+        }
+    };
+    for (ps, pe) in pieces {
+        split_span(text, ps, pe, level + 1, cfg, tc, out);
+    }
+}
+
+/// Единицы разбиения большого code fence, каждая влезает в лимиты:
+/// верхнеуровневые элементы (функции, классы, impl...) для известных языков,
+/// затем пустые строки, затем отдельные строки, затем жёсткая нарезка.
+fn code_spans(text: &str, lang: Option<&str>, cfg: &ChunkingConfig, tc: &TokenCounter) -> Vec<(usize, usize)> {
+    let mut out = Vec::new();
+    split_code_span(text, 0, text.len(), lang, 0, cfg, tc, &mut out);
+    out
+}
+
+#[allow(clippy::too_many_arguments)]
+fn split_code_span(
+    text: &str,
+    s: usize,
+    e: usize,
+    lang: Option<&str>,
+    level: u8,
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+    out: &mut Vec<(usize, usize)>,
+) {
+    if fits(&text[s..e], cfg, tc) {
+        out.push((s, e));
+        return;
+    }
+    let cuts = match level {
+        0 => item_cuts(text, s, e, lang),
+        1 => line_cuts(text, s, e, |prev, _| prev.trim().is_empty()),
+        2 => line_cuts(text, s, e, |_, _| true),
+        _ => {
+            hard_cut(text, s, e, cfg, tc, out);
+            return;
+        }
+    };
+    let mut start = s;
+    for cut in cuts.into_iter().chain(std::iter::once(e)) {
+        if cut > start {
+            split_code_span(text, start, cut, lang, level + 1, cfg, tc, out);
+            start = cut;
+        }
+    }
+}
+
+/// Начала строк внутри `text[s..e]` (кроме первой), перед которыми `is_cut(prev_line, line)` истинно.
+fn line_cuts(text: &str, s: usize, e: usize, is_cut: impl Fn(&str, &str) -> bool) -> Vec<usize> {
+    let mut cuts = Vec::new();
+    let mut prev: Option<&str> = None;
+    let mut pos = s;
+    for line in text[s..e].split_inclusive('\n') {
+        if let Some(p) = prev {
+            if is_cut(p, line) {
+                cuts.push(pos);
+            }
+        }
+        prev = Some(line);
+        pos += line.len();
+    }
+    cuts
+}
+
+/// Границы верхнеуровневых элементов кода; комментарии и атрибуты
+/// непосредственно над элементом остаются с ним.
+fn item_cuts(text: &str, s: usize, e: usize, lang: Option<&str>) -> Vec<usize> {
+    let Some(re) = lang.and_then(item_start_regex) else {
+        return Vec::new();
+    };
+    let mut lines: Vec<(usize, &str)> = Vec::new();
+    let mut pos = s;
+    for line in text[s..e].split_inclusive('\n') {
+        lines.push((pos, line));
+        pos += line.len();
+    }
+
+    let mut cuts = Vec::new();
+    for idx in 1..lines.len() {
+        if !re.is_match(lines[idx].1) {
+            continue;
+        }
+        let mut first = idx;
+        while first > 1 && is_code_preamble(lines[first - 1].1) {
+            first -= 1;
+        }
+        if cuts.last().is_none_or(|&c| c < lines[first].0) {
+            cuts.push(lines[first].0);
+        }
+    }
+    cuts
+}
+
+fn is_code_preamble(line: &str) -> bool {
+    let t = line.trim_start();
+    !t.trim().is_empty() && ["//", "#", "@", "/*", "*", "--"].iter().any(|p| t.starts_with(p))
+}
+
+fn item_start_regex(lang: &str) -> Option<&'static Regex> {
+    match lang {
+        "rust" | "rs" => Some(&RE_ITEM_RUST),
+        "python" | "py" => Some(&RE_ITEM_PYTHON),
+        "javascript" | "js" | "jsx" | "typescript" | "ts" | "tsx" => Some(&RE_ITEM_JS),
+        "go" | "golang" => Some(&RE_ITEM_GO),
+        "java" | "kotlin" | "kt" | "scala" | "csharp" | "cs" | "c" | "cpp" | "c++" | "h" | "hpp" | "swift"
+        | "php" => Some(&RE_ITEM_BRACE),
+        _ => None,
+    }
+}
+
+/// Режет `text[s..e]` после каждого символа, для которого `is_cut(c, next)` истинно.
+fn cut_after(text: &str, s: usize, e: usize, is_cut: impl Fn(char, Option<char>) -> bool) -> Vec<(usize, usize)> {
+    let mut out = Vec::new();
+    let mut start = s;
+    let mut it = text[s..e].char_indices().peekable();
+    while let Some((i, c)) = it.next() {
+        let next = it.peek().map(|&(_, n)| n);
+        if is_cut(c, next) {
+            let end = s + i + c.len_utf8();
+            out.push((start, end));
+            start = end;
+        }
+    }
+    if start < e {
+        out.push((start, e));
+    }
+    out
+}
+
+/// Последний рубеж: куски фиксированной длины по границам символов
+/// (по смещениям токенов, если доступен токенайзер).
+fn hard_cut(
+    text: &str,
+    s: usize,
+    e: usize,
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+    out: &mut Vec<(usize, usize)>,
+) {
+    let max_bytes = cfg.hard_max_bytes.max(4);
+    let cuts: Vec<usize> = match tc.token_starts(&text[s..e]) {
+        Some(starts) => starts
+            .into_iter()
+            .skip(cfg.max_tokens.max(1))
+            .step_by(cfg.max_tokens.max(1))
+            .map(|off| s + off)
+            .collect(),
+        None => {
+            let max_chars = ((cfg.max_tokens as f32) * tc.approx_chars_per_token)
+                .floor()
+                .max(1.0) as usize;
+            text[s..e]
+                .char_indices()
+                .skip(max_chars)
+                .step_by(max_chars)
+                .map(|(i, _)| s + i)
+                .collect()
+        }
+    };
+
+    let mut start = s;
+    let mut cuts = cuts.into_iter().filter(|&c| c > s && c < e && text.is_char_boundary(c)).peekable();
+    for (i, c) in text[s..e].char_indices() {
+        let pos = s + i;
+        let at_cut = cuts.peek() == Some(&pos);
+        if at_cut {
+            cuts.next();
+        }
+        if pos > start && (at_cut || pos + c.len_utf8() - start > max_bytes) {
+            out.push((start, pos));
+            start = pos;
+        }
+    }
+    if start < e {
+        out.push((start, e));
+    }
+}
+
+/// Начало хвоста `text` из целых предложений, укладывающегося в `budget` токенов.
+fn tail_overlap_start(text: &str, budget: usize, tc: &TokenCounter) -> Option<usize> {
+    let mut start = None;
+    let mut used = 0;
+    for &(s, e) in sentence_spans(text).iter().rev() {
+        used += tc.count(&text[s..e]);
+        if used > budget {
+            break;
+        }
+        start = Some(s);
+    }
+    start
+}
+
+fn chunk_id(doc_id: &str, start: usize, end: usize, text: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}:{}:{}:{}", doc_id, start, end, text).as_bytes());
+    let digest = hasher.finalize();
+    format!("chunk::{:x}", digest)
+}
+
+pub fn chunk_document(doc_id: &str, input: &str, cfg: &ChunkingConfig) -> Vec<Chunk> {
+    chunk_document_with_tokenizer(doc_id, input, cfg, None)
+}
+
+/// Как `chunk_document`, но размер чанков считается токенайзером модели (если передан).
+pub fn chunk_document_with_tokenizer(
+    doc_id: &str,
+    input: &str,
+    cfg: &ChunkingConfig,
+    tokenizer: Option<&Tokenizer>,
+) -> Vec<Chunk> {
+    let tc = match tokenizer {
+        Some(t) => TokenCounter::with_tokenizer(t, cfg.approx_chars_per_token),
+        None => TokenCounter::approx(cfg.approx_chars_per_token),
+    };
+    chunk_document_with_counter(doc_id, input, cfg, &tc)
+}
+
+pub fn chunk_document_with_counter(
+    doc_id: &str,
+    input: &str,
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+) -> Vec<Chunk> {
+    let blocks = parse_blocks(input);
+    make_chunks_with(doc_id, input, &blocks, cfg, tc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_basic() {
+        let doc = "# Заголовок\n\nПараграф текста.";
+        let cfg = ChunkingConfig::default();
+        let ch = chunk_document("doc::1", doc, &cfg);
+        assert!(!ch.is_empty());
+
+        let blocks = parse_blocks(doc);
+        let texts = |c: &[Chunk]| c.iter().map(|c| c.text.clone()).collect::<Vec<_>>();
+        assert_eq!(texts(&make_chunks("doc::1", doc, &blocks, &cfg)), texts(&ch));
+    }
+
+    #[test]
+    fn test_overlap_windows() {
+        let doc = "Первое предложение про Rust. Второе предложение про Qdrant. \
+                   Третье предложение про эмбеддинги. Четвёртое предложение про чанки.";
+        let cfg = ChunkingConfig {
+            max_tokens: 20,
+            overlap_tokens: 10,
+            ..Default::default()
+        };
+        let ch = chunk_document("doc::1", doc, &cfg);
+        assert!(ch.len() > 1);
+        for c in &ch {
+            assert_eq!(&doc[c.start..c.end], c.text);
+        }
+        // Consecutive windows share whole sentences
+        for w in ch.windows(2) {
+            assert!(w[1].start < w[0].end);
+        }
+    }
+
+    #[test]
+    fn test_no_overlap() {
+        let doc = "Первое предложение про Rust. Второе предложение про Qdrant. \
+                   Третье предложение про эмбеддинги. Четвёртое предложение про чанки.";
+        let cfg = ChunkingConfig {
+            max_tokens: 20,
+            overlap_tokens: 0,
+            ..Default::default()
+        };
+        let ch = chunk_document("doc::1", doc, &cfg);
+        for w in ch.windows(2) {
+            assert!(w[1].start >= w[0].end);
+        }
+    }
+
+    #[test]
+    fn test_heading_path() {
+        let doc = "# Section 2\n\n## Subsection 2.1\n\nПараграф внутри подраздела про индексацию.\n\n\
+                   # Section 3\n\nПараграф в третьем разделе документа про поиск.";
+        let ch = chunk_document("doc::1", doc, &ChunkingConfig::default());
+        assert_eq!(ch[0].headings, vec!["Section 2", "Subsection 2.1"]);
+        assert_eq!(ch[1].headings, vec!["Section 3"]);
+        assert!(passage_text(&ch[0], &ChunkingConfig::default()).starts_with("Section 2 > Subsection 2.1"));
+    }
+
+    #[test]
+    fn test_hard_limits() {
+        let wall = "слово без запятых и точек ".repeat(400);
+        let cfg = ChunkingConfig {
+            max_tokens: 50,
+            overlap_tokens: 10,
+            hard_max_bytes: 300,
+            ..Default::default()
+        };
+        let ch = chunk_document("doc::1", &wall, &cfg);
+        assert!(ch.len() > 1);
+        for c in &ch {
+            assert!(c.text.len() <= cfg.hard_max_bytes);
+            assert!(approx_tokens(&c.text, cfg.approx_chars_per_token) <= cfg.max_tokens);
+            assert_eq!(&wall[c.start..c.end], c.text);
+        }
+
+        let blob = "ж".repeat(5000);
+        for c in chunk_document("doc::2", &blob, &cfg) {
+            assert!(c.text.len() <= cfg.hard_max_bytes);
+        }
+    }
+
+    #[test]
+    fn test_tokenizer_counting() {
+        use tokenizers::models::wordlevel::WordLevel;
+        use tokenizers::pre_tokenizers::whitespace::WhitespaceSplit;
+
+        let vocab = [("[UNK]".to_string(), 0u32)].into_iter().collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(WhitespaceSplit);
+
+        // One token per word: "ж" * 40 is one token for the tokenizer, ten for the approximation
+        let tc = TokenCounter::with_tokenizer(&tokenizer, 4.0);
+        assert_eq!(tc.count("один два три"), 3);
+        assert_eq!(tc.count(&"ж".repeat(40)), 1);
+
+        let doc = (0..16).map(|i| format!("indexing{:02}", i)).collect::<Vec<_>>().join(" ");
+        let cfg = ChunkingConfig {
+            max_tokens: 4,
+            overlap_tokens: 0,
+            ..Default::default()
+        };
+        let ch = chunk_document_with_tokenizer("doc::1", &doc, &cfg, Some(&tokenizer));
+        assert_eq!(ch.len(), 4);
+        for c in &ch {
+            assert_eq!(tc.count(&c.text), 4);
+        }
+    }
+
+    #[test]
+    fn test_code_fence_split_by_items() {
+        let mut doc = String::from("```rust\n");
+        for i in 0..6 {
+            doc.push_str(&format!(
+                "/// Doc for f{i}.\nfn f{i}(x: u32) -> u32 {{\n    let y = x.wrapping_mul({i});\n    y + 1\n}}\n\n"
+            ));
+        }
+        doc.push_str("```\n");
+        let cfg = ChunkingConfig {
+            max_tokens: 40,
+            overlap_tokens: 0,
+            ..Default::default()
+        };
+        let ch = chunk_document("doc::1", &doc, &cfg);
+        assert!(ch.len() > 1);
+        for c in &ch {
+            assert_eq!(c.lang.as_deref(), Some("rust"));
+            // Cuts land on item boundaries, never inside a function body
+            assert_eq!(c.text.matches('{').count(), c.text.matches('}').count());
+        }
+        assert!(ch[1].text.starts_with("/// Doc for"));
+    }
+
+    #[test]
+    fn test_front_matter() {
+        let doc = "---\ntitle: \"Mixed Torture Case\"\nauthor: \"nooforge\"\ntags: [test, chaos]\n---\n\n\
+                   Vectorized aggregation keeps columns compressed and operates in batches.";
+        let (end, meta) = parse_front_matter(doc).unwrap();
+        assert_eq!(meta.title.as_deref(), Some("Mixed Torture Case"));
+        assert_eq!(meta.author.as_deref(), Some("nooforge"));
+        assert_eq!(meta.tags, vec!["test", "chaos"]);
+        assert!(doc[end..].trim_start().starts_with("Vectorized"));
+
+        let ch = chunk_document("doc::1", doc, &ChunkingConfig::default());
+        assert!(ch.iter().all(|c| !c.text.contains("title:")));
+
+        let toml_doc = "+++\ntitle = \"Notes\"\ntags = [\"a\"]\ndate = \"2024-01-01\"\n+++\nBody";
+        let (_, meta) = parse_front_matter(toml_doc).unwrap();
+        assert_eq!(meta.title.as_deref(), Some("Notes"));
+        assert_eq!(meta.extra["date"], "2024-01-01");
+
+        assert!(parse_front_matter("---\n\nJust an HR above").is_none());
+    }
+
+    #[test]
+    fn test_small_blocks_merged() {
+        let doc = "# Итоги\n\nКоротко.\n\n- да\n- нет\n\nТермин: определение.\n\n# Next\n\nok";
+        let ch = chunk_document("doc::1", doc, &ChunkingConfig::default());
+        assert_eq!(ch.len(), 2);
+        assert_eq!(
+            ch[0].kind_summary,
+            vec![BlockKind::Header, BlockKind::Paragraph, BlockKind::List]
+        );
+        assert!(ch[0].text.contains("Термин"));
+        assert_eq!(ch[1].text, "# Next\n\nok");
+        assert_eq!(ch[1].headings, vec!["Next"]);
+    }
+
+    #[test]
+    fn test_table_split_repeats_header() {
+        let mut doc = String::from("| name | value |\n|------|-------|\n");
+        for i in 0..40 {
+            doc.push_str(&format!("| row{:02} | значение {:02} |\n", i, i));
+        }
+        let cfg = ChunkingConfig {
+            max_tokens: 60,
+            overlap_tokens: 0,
+            ..Default::default()
+        };
+        let ch = chunk_document("doc::1", &doc, &cfg);
+        assert!(ch.len() > 2);
+        for c in &ch {
+            assert!(c.text.starts_with("| name | value |\n|------|-------|\n"));
+            assert!(approx_tokens(&c.text, cfg.approx_chars_per_token) <= cfg.max_tokens);
+            // Rows are never cut in the middle
+            assert!(c.text.lines().all(|l| l.starts_with('|') && l.ends_with('|')));
+        }
+    }
+
+    #[test]
+    fn test_table_budget_and_wide_rows() {
+        let header = "| id | note | extra |\n|----|------|-------|\n";
+        let wide = format!("| r1 | {} | {} |", "слово ".repeat(40).trim_end(), "word ".repeat(40).trim_end());
+        let prose = "Intro sentence number one. ".repeat(12);
+        let doc = format!("{}\n\n{}{}\n| r2 | short | x |\n", prose.trim_end(), header, wide);
+        let cfg = ChunkingConfig {
+            max_tokens: 80,
+            overlap_tokens: 0,
+            hard_max_bytes: 600,
+            ..Default::default()
+        };
+        let tc = TokenCounter::approx(cfg.approx_chars_per_token);
+        let ch = chunk_document_with_counter("doc::1", &doc, &cfg, &tc);
+        // Prose is packed with the full budget, not the table's reduced one
+        let head = header.trim_end();
+        let table_chunks: Vec<&Chunk> = ch.iter().filter(|c| c.text.starts_with(head)).collect();
+        assert!(table_chunks.len() >= 2);
+        assert!(tc.count(&ch[0].text) > cfg.max_tokens - tc.count(head));
+        for c in ch.iter() {
+            assert!(fits(&c.text, &cfg, &tc), "{}", c.text);
+        }
+        for c in table_chunks {
+            // Wide rows are cut at cell borders, so every line stays a table row
+            assert!(c.text.lines().all(|l| l.starts_with('|') && l.ends_with('|')), "{}", c.text);
+        }
+        assert!(ch.iter().any(|c| c.text.contains("| r2 | short | x |")));
+    }
+
+    #[test]
+    fn test_list_split_keeps_nested_items() {
+        let mut doc = String::new();
+        for i in 0..12 {
+            doc.push_str(&format!("- item {} about indexing pipelines\n  - nested {} detail\n", i, i));
+        }
+        let cfg = ChunkingConfig {
+            max_tokens: 40,
+            overlap_tokens: 0,
+            ..Default::default()
+        };
+        let ch = chunk_document("doc::1", &doc, &cfg);
+        assert!(ch.len() > 1);
+        for c in &ch {
+            assert!(c.text.starts_with("- item"));
+            assert_eq!(c.text.matches("- item").count(), c.text.matches("- nested").count());
+        }
+    }
+}
@@ -0,0 +1,161 @@
+// file: src/chunking/position.rs
+//
+// Строки, колонки и символьные смещения для байтовых спанов чанков:
+// UI показывают и подсвечивают место в исходнике без повторного декодирования файла.
+
+use serde::{Deserialize, Serialize};
+
+use super::Chunk;
+
+/// Положение чанка в тексте. Строки и колонки с 1, колонки и смещения —
+/// в Unicode-символах; конец исключающий (позиция символа сразу после чанка).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpanPos {
+    pub start_line: usize,
+    pub start_col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+    pub char_start: usize,
+    pub char_end: usize,
+}
+
+/// Точка в тексте: строка и колонка (с 1) и смещение в символах.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct TextPos {
+    pub line: usize,
+    pub col: usize,
+    pub char_offset: usize,
+}
+
+impl TextPos {
+    pub const START: TextPos = TextPos {
+        line: 1,
+        col: 1,
+        char_offset: 0,
+    };
+
+    /// Позиция `rel`, посчитанная от начала куска, который сам начинается в `self`.
+    pub fn advance(self, rel: TextPos) -> TextPos {
+        TextPos {
+            line: self.line + rel.line - 1,
+            col: if rel.line == 1 { self.col + rel.col - 1 } else { rel.col },
+            char_offset: self.char_offset + rel.char_offset,
+        }
+    }
+}
+
+/// Шаг контрольных точек символьных смещений: колонка в длинной строке
+/// (минифицированный JSON, логи) считается от ближайшей точки, а не от начала строки.
+const CHECKPOINT_BYTES: usize = 4096;
+
+/// Индекс начал строк для перевода байтовых смещений в `TextPos`.
+pub(crate) struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+    chars_before: Vec<usize>,
+    /// `(байт, символов до него)` примерно через каждые `CHECKPOINT_BYTES`
+    checkpoints: Vec<(usize, usize)>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        let mut chars_before = vec![0];
+        let mut checkpoints = vec![(0, 0)];
+        let mut chars = 0;
+        for (i, c) in text.char_indices() {
+            if i >= checkpoints[checkpoints.len() - 1].0 + CHECKPOINT_BYTES {
+                checkpoints.push((i, chars));
+            }
+            chars += 1;
+            if c == '\n' {
+                line_starts.push(i + 1);
+                chars_before.push(chars);
+            }
+        }
+        Self {
+            text,
+            line_starts,
+            chars_before,
+            checkpoints,
+        }
+    }
+
+    pub fn pos(&self, byte: usize) -> TextPos {
+        let byte = byte.min(self.text.len());
+        let line = self.line_starts.partition_point(|&s| s <= byte) - 1;
+        let line_start = self.line_starts[line];
+        // Count from the line start or a later checkpoint, whichever is closer
+        let (from, chars) = match self.checkpoints[self.checkpoints.partition_point(|&(b, _)| b <= byte) - 1] {
+            (b, c) if b > line_start => (b, c),
+            _ => (line_start, self.chars_before[line]),
+        };
+        let char_offset = chars + self.text[from..byte].chars().count();
+        let col = char_offset - self.chars_before[line];
+        TextPos {
+            line: line + 1,
+            col: col + 1,
+            char_offset,
+        }
+    }
+
+    pub fn span(&self, start: usize, end: usize) -> SpanPos {
+        span_pos(self.pos(start), self.pos(end))
+    }
+}
+
+pub(crate) fn span_pos(start: TextPos, end: TextPos) -> SpanPos {
+    SpanPos {
+        start_line: start.line,
+        start_col: start.col,
+        end_line: end.line,
+        end_col: end.col,
+        char_start: start.char_offset,
+        char_end: end.char_offset,
+    }
+}
+
+/// Заполняет `pos` у чанков, спаны которых указывают в `input`.
+pub fn locate_chunks(input: &str, chunks: &mut [Chunk]) {
+    let index = LineIndex::new(input);
+    for chunk in chunks {
+        chunk.pos = index.span(chunk.start, chunk.end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index() {
+        let text = "ab\nпривет мир\n\nxyz";
+        let index = LineIndex::new(text);
+        let start = text.find("мир").unwrap();
+        let span = index.span(start, text.len());
+        assert_eq!((span.start_line, span.start_col), (2, 8));
+        assert_eq!((span.end_line, span.end_col), (4, 4));
+        assert_eq!(span.char_start, 10);
+        assert_eq!(span.char_end, text.chars().count());
+
+        // Piece-relative positions shift onto the piece start
+        let piece = &text[start..];
+        let rel = LineIndex::new(piece).pos(piece.len() - 2);
+        assert_eq!(index.pos(start).advance(rel), index.pos(text.len() - 2));
+        let rel = LineIndex::new(piece).pos(2);
+        assert_eq!(index.pos(start).advance(rel), index.pos(start + 2));
+    }
+
+    #[test]
+    fn test_long_line_checkpoints() {
+        let text = format!("x\n{}", "{\"ключ\":1},".repeat(2000));
+        let index = LineIndex::new(&text);
+        assert!(index.checkpoints.len() > 1);
+        for byte in (0..=text.len()).filter(|&b| text.is_char_boundary(b)).step_by(97) {
+            let chars = text[..byte].chars().count();
+            let pos = index.pos(byte);
+            assert_eq!(pos.char_offset, chars);
+            assert_eq!((pos.line, pos.col), if byte < 2 { (1, chars + 1) } else { (2, chars - 1) });
+        }
+    }
+}
@@ -0,0 +1,204 @@
+// file: src/chunking/semantic.rs
+//
+// Семантический чанкинг (аналог SemanticSplitter из llamaindex):
+// эмбеддим предложения и режем там, где сходство соседей резко падает.
+
+use anyhow::Result;
+
+use super::{
+    chunk_id, code_spans, locate_chunks, pack_windows, parse_blocks, parse_header, sentence_spans, split_oversize,
+    BlockKind, Chunk, ChunkingConfig, SpanPos, TokenCounter,
+};
+
+/// Единица семантического разбиения: предложение (или кусок кода) с контекстом блока.
+#[derive(Debug, Clone)]
+struct Unit {
+    start: usize,
+    end: usize,
+    kind: BlockKind,
+    lang: Option<String>,
+    headings: Vec<String>,
+}
+
+/// Чанкинг по смыслу: `embed` вызывается для каждого предложения вместе с
+/// `cfg.semantic_buffer_size` соседями с каждой стороны; разрыв ставится там, где
+/// косинусное расстояние между соседями выше перцентиля `cfg.semantic_breakpoint_percentile`.
+/// Заголовки всегда начинают новый чанк, лимиты `max_tokens`/`hard_max_bytes` соблюдаются.
+pub fn semantic_chunks<F>(
+    doc_id: &str,
+    input: &str,
+    cfg: &ChunkingConfig,
+    tc: &TokenCounter,
+    mut embed: F,
+) -> Result<Vec<Chunk>>
+where
+    F: FnMut(&str) -> Result<Vec<f32>>,
+{
+    let units = collect_units(input, cfg, tc);
+    if units.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Embeddings of each unit with its neighbours
+    let buf = cfg.semantic_buffer_size;
+    let mut embeddings = Vec::with_capacity(units.len());
+    for i in 0..units.len() {
+        let lo = i.saturating_sub(buf);
+        let hi = (i + buf).min(units.len() - 1);
+        embeddings.push(embed(&input[units[lo].start..units[hi].end])?);
+    }
+
+    let distances: Vec<f32> = embeddings
+        .windows(2)
+        .map(|w| 1.0 - cosine(&w[0], &w[1]))
+        .collect();
+    let threshold = percentile(&distances, cfg.semantic_breakpoint_percentile);
+
+    // Group boundaries: semantic breakpoints plus every heading
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    let mut first = 0;
+    for i in 1..units.len() {
+        let semantic_break = distances[i - 1] > threshold;
+        if semantic_break || units[i].kind == BlockKind::Header {
+            groups.push((first, i));
+            first = i;
+        }
+    }
+    groups.push((first, units.len()));
+
+    let mut chunks = Vec::new();
+    for (lo, hi) in groups {
+        let group = &units[lo..hi];
+        let spans: Vec<(usize, usize)> = group.iter().map(|u| (u.start, u.end)).collect();
+
+        let mut kind_summary: Vec<BlockKind> = Vec::new();
+        for u in group {
+            if !kind_summary.contains(&u.kind) {
+                kind_summary.push(u.kind);
+            }
+        }
+        let lang = group[0].lang.clone().filter(|l| group.iter().all(|u| u.lang.as_ref() == Some(l)));
+
+        for (start, end) in pack_windows(input, &spans, cfg, tc) {
+            let text = &input[start..end];
+            if text.trim().is_empty() {
+                continue;
+            }
+            // Headings of the unit the window starts with
+            let headings = group
+                .iter()
+                .find(|u| u.start >= start)
+                .map(|u| u.headings.clone())
+                .unwrap_or_default();
+            chunks.push(Chunk {
+                id: chunk_id(doc_id, start, end, text),
+                kind_summary: kind_summary.clone(),
+                start,
+                end,
+                text: text.to_string(),
+                headings,
+                lang: lang.clone(),
+                pos: SpanPos::default(),
+            });
+        }
+    }
+
+    locate_chunks(input, &mut chunks);
+    Ok(chunks)
+}
+
+/// Предложения всех блоков документа в порядке следования (абсолютные смещения).
+fn collect_units(input: &str, cfg: &ChunkingConfig, tc: &TokenCounter) -> Vec<Unit> {
+    let mut units = Vec::new();
+    let mut heading_stack: Vec<(usize, String)> = Vec::new();
+
+    for b in parse_blocks(input) {
+        let text = &input[b.start..b.end];
+        if text.trim().is_empty() {
+            continue;
+        }
+        if b.kind == BlockKind::Header {
+            if let Some((level, title)) = parse_header(text) {
+                heading_stack.retain(|(l, _)| *l < level);
+                heading_stack.push((level, title));
+            }
+        }
+
+        let spans = if b.kind == BlockKind::CodeFence {
+            code_spans(text, b.lang.as_deref(), cfg, tc)
+        } else {
+            split_oversize(text, &sentence_spans(text), cfg, tc)
+        };
+        let headings: Vec<String> = heading_stack.iter().map(|(_, t)| t.clone()).collect();
+        for (s, e) in spans {
+            units.push(Unit {
+                start: b.start + s,
+                end: b.start + e,
+                kind: b.kind,
+                lang: b.lang.clone(),
+                headings: headings.clone(),
+            });
+        }
+    }
+
+    units
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let na: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let nb: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if na == 0.0 || nb == 0.0 {
+        0.0
+    } else {
+        dot / (na * nb)
+    }
+}
+
+/// Перцентиль с линейной интерполяцией (как `numpy.percentile`).
+fn percentile(values: &[f32], p: f32) -> f32 {
+    if values.is_empty() {
+        return f32::INFINITY;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let rank = (p.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f32;
+    let lo = rank.floor() as usize;
+    let hi = rank.ceil() as usize;
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (rank - lo as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_semantic_breakpoints() {
+        let doc = "Rust ownership prevents data races at compile time. \
+                   The borrow checker enforces aliasing rules for references. \
+                   Bananas are rich in potassium and easy to digest. \
+                   Ripe bananas turn yellow and develop brown spots.";
+        // Toy embedding: topic axis by keyword
+        let embed = |s: &str| -> Result<Vec<f32>> {
+            let rust = s.matches("Rust").count() + s.matches("borrow").count();
+            let fruit = s.matches("anana").count();
+            Ok(vec![rust as f32, fruit as f32])
+        };
+        let cfg = ChunkingConfig {
+            semantic_buffer_size: 0,
+            semantic_breakpoint_percentile: 50.0,
+            ..Default::default()
+        };
+        let tc = TokenCounter::approx(cfg.approx_chars_per_token);
+        let ch = semantic_chunks("doc::1", doc, &cfg, &tc, embed).unwrap();
+        assert_eq!(ch.len(), 2);
+        assert!(ch[0].text.contains("borrow checker"));
+        assert!(ch[1].text.starts_with("Bananas"));
+    }
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile(&[1.0, 2.0, 3.0, 4.0, 5.0], 50.0), 3.0);
+        assert_eq!(percentile(&[0.0, 10.0], 95.0), 9.5);
+    }
+}
@@ -0,0 +1,228 @@
+// file: src/chunking/sentences.rs
+//
+// Сегментация на предложения для русского и английского текста:
+// сокращения («т. е.», «e.g.»), инициалы, числа, URL, многоточия,
+// и разбиение по строкам для текста без пунктуации.
+
+use std::collections::HashSet;
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// Сокращения (в нижнем регистре, без финальной точки), после которых точка
+    /// не конец предложения. Только формы, которыми предложение не кончается:
+    /// обычные слова ("no", "co", "est") сюда не попадают.
+    static ref ABBREVIATIONS: HashSet<&'static str> = [
+        // English
+        "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "vs", "fig", "figs", "nos", "vol", "vols",
+        "pp", "approx", "dept", "cf", "e.g", "i.e", "ph.d", "jan", "feb", "apr", "jun", "jul",
+        "aug", "sep", "sept", "oct", "nov", "dec", "mt", "gen", "gov", "rev",
+        // Русские
+        "т.е", "т.к", "т.н", "т.ч", "т.о", "и.о", "см", "рис", "стр", "табл", "ул", "пр", "им",
+        "проф", "акад", "доц", "напр", "ср", "кв", "обл", "пос", "гл", "разд", "прим", "ред",
+        "изд",
+    ]
+    .into_iter()
+    .collect();
+}
+
+/// Сокращения из двух слов через пробел («т. к.», «et al.»): точка после
+/// первого или второго слова не конец предложения.
+const MULTI_ABBREVIATIONS: &[(&str, &str)] = &[
+    ("т", "е"),
+    ("т", "к"),
+    ("т", "н"),
+    ("т", "ч"),
+    ("т", "о"),
+    ("и", "о"),
+    ("et", "al"),
+];
+
+/// Закрывающие кавычки и скобки, которые остаются с предыдущим предложением.
+fn is_closer(c: char) -> bool {
+    matches!(c, '"' | '\'' | ')' | ']' | '»' | '”' | '’' | '“')
+}
+
+fn is_terminator(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '…')
+}
+
+/// Байтовые диапазоны предложений; хвостовые пробелы входят в предложение,
+/// пустые фрагменты пропускаются.
+pub fn sentence_spans(text: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut out = Vec::new();
+    let mut start = 0usize;
+    let mut i = 0usize;
+
+    while i < chars.len() {
+        let (pos, c) = chars[i];
+        if !is_terminator(c) {
+            i += 1;
+            continue;
+        }
+
+        // Terminator run ("?!", "...", "…") followed by closing quotes/brackets
+        let run_start = i;
+        let mut j = i;
+        while j < chars.len() && is_terminator(chars[j].1) {
+            j += 1;
+        }
+        while j < chars.len() && is_closer(chars[j].1) {
+            j += 1;
+        }
+        let run: String = chars[run_start..j].iter().map(|&(_, ch)| ch).collect();
+
+        // A boundary needs whitespace (or the end of text) after the run:
+        // this keeps "3.14", "v1.2.3", "example.com/x" and "e.g.," intact
+        let mut k = j;
+        while k < chars.len() && chars[k].1.is_whitespace() {
+            k += 1;
+        }
+        let at_end = k == chars.len();
+        let followed_by_space = k > j;
+        let next = chars.get(k).map(|&(_, ch)| ch);
+
+        // Abbreviations only matter for a single dot, not for "..." or "?"
+        let abbreviation = run.starts_with('.') && !run.starts_with("..") && is_abbreviation(text, pos);
+        let boundary = at_end
+            || (followed_by_space
+                && !next.is_some_and(char::is_lowercase)
+                && !abbreviation
+                && !is_list_marker(text, pos));
+
+        if boundary {
+            let end = chars.get(k).map_or(text.len(), |&(p, _)| p);
+            push_span(text, start, end, &mut out);
+            start = end;
+        }
+        i = k.max(i + 1);
+    }
+    push_span(text, start, text.len(), &mut out);
+
+    newline_fallback(text, out)
+}
+
+/// Предложения как подстроки `text`.
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    sentence_spans(text).into_iter().map(|(s, e)| &text[s..e]).collect()
+}
+
+fn push_span(text: &str, start: usize, end: usize, out: &mut Vec<(usize, usize)>) {
+    if start < end && !text[start..end].trim().is_empty() {
+        out.push((start, end));
+    }
+}
+
+/// Слово перед точкой на позиции `dot` — сокращение или инициал?
+fn is_abbreviation(text: &str, dot: usize) -> bool {
+    let before = &text[..dot];
+    let word_start = before
+        .char_indices()
+        .rev()
+        .find(|&(_, c)| !(c.is_alphanumeric() || c == '.'))
+        .map_or(0, |(p, c)| p + c.len_utf8());
+    let word = &before[word_start..];
+    if word.is_empty() || word.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    let word = word.to_lowercase();
+    let prev = before[..word_start].split_whitespace().next_back().unwrap_or("");
+    let next = text[dot + 1..].split_whitespace().next().unwrap_or("");
+
+    // "т. к." / "et al.": the first word followed by the second, or the second after the first
+    let next_lower = next.to_lowercase();
+    let prev_lower = prev.to_lowercase();
+    if MULTI_ABBREVIATIONS.iter().any(|&(a, b)| {
+        (word == a && next_lower.strip_prefix(b).is_some_and(|r| r.starts_with('.')))
+            || (word == b && prev_lower.trim_end_matches('.') == a)
+    }) {
+        return true;
+    }
+
+    // Initials: "J. R. R. Tolkien", "А. С. Пушкин" — an uppercase letter before
+    // another initial, or the last initial before a capitalized name
+    if word.chars().count() == 1 {
+        let upper = before[word_start..].chars().all(char::is_uppercase);
+        let capitalized = next.chars().next().is_some_and(char::is_uppercase);
+        return upper && (is_initial(next) || (is_initial(prev) && capitalized));
+    }
+    ABBREVIATIONS.contains(word.as_str())
+}
+
+/// Инициал: одна заглавная буква с точкой ("А.", "J.").
+fn is_initial(token: &str) -> bool {
+    let mut chars = token.chars();
+    matches!((chars.next(), chars.next(), chars.next()), (Some(c), Some('.'), None) if c.is_uppercase())
+}
+
+/// "1. Пункт" в начале строки — маркер нумерованного списка, а не конец предложения.
+fn is_list_marker(text: &str, dot: usize) -> bool {
+    let line_start = text[..dot].rfind('\n').map_or(0, |p| p + 1);
+    let prefix = text[line_start..dot].trim_start();
+    !prefix.is_empty() && prefix.len() <= 3 && prefix.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Фрагменты без единого знака конца предложения, занимающие несколько строк,
+/// режутся по переводам строк (текст без пунктуации).
+fn newline_fallback(text: &str, spans: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut out = Vec::with_capacity(spans.len());
+    for (s, e) in spans {
+        let piece = &text[s..e];
+        if piece.chars().any(is_terminator) || !piece.trim().contains('\n') {
+            out.push((s, e));
+            continue;
+        }
+        let mut start = s;
+        let mut pos = s;
+        for line in piece.split_inclusive('\n') {
+            pos += line.len();
+            if line.ends_with('\n') && pos < e && !text[pos..e].trim().is_empty() {
+                push_span(text, start, pos, &mut out);
+                start = pos;
+            }
+        }
+        push_span(text, start, e, &mut out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_abbreviations_and_numbers() {
+        let text = "Используем e.g. векторы, т.е. эмбеддинги, т. к. это быстро. \
+                    Число π ≈ 3.14 и версия 1.2.3 работают. А. С. Пушкин писал стихи! \
+                    See https://example.com/a.b for details. Dr. Smith agrees.";
+        let s = split_sentences(text);
+        assert_eq!(s.len(), 5, "{:?}", s);
+        assert!(s[0].trim_end().ends_with("быстро."));
+        assert!(s[2].starts_with("А. С. Пушкин"));
+        assert!(s[4].starts_with("Dr. Smith"));
+    }
+
+    #[test]
+    fn test_plain_words_end_sentences() {
+        assert_eq!(split_sentences("The answer was no. We left."), vec!["The answer was no. ", "We left."]);
+        assert_eq!(split_sentences("Это сделал я. Потом ушёл."), vec!["Это сделал я. ", "Потом ушёл."]);
+        assert_eq!(split_sentences("So did I. Then we ate."), vec!["So did I. ", "Then we ate."]);
+        assert_eq!(split_sentences("J. R. R. Tolkien and Smith et al. wrote it.").len(), 1);
+        assert_eq!(split_sentences("Он ушёл, т. е. Иван остался.").len(), 1);
+    }
+
+    #[test]
+    fn test_ellipsis_and_quotes() {
+        let s = split_sentences("Ну... может быть. «Точно?» Да… Конечно.");
+        assert_eq!(s, vec!["Ну... может быть. ", "«Точно?» ", "Да… ", "Конечно."]);
+    }
+
+    #[test]
+    fn test_newline_fallback() {
+        let text = "this text has no commas or dots\nsometimes even linebreaks are missing\nпроверим кириллицу";
+        assert_eq!(split_sentences(text).len(), 3);
+        // Hard-wrapped punctuated prose stays whole
+        assert_eq!(split_sentences("A wrapped\nsentence here. Next one\nwraps too.").len(), 2);
+    }
+}
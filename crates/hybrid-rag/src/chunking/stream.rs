@@ -0,0 +1,430 @@
+// file: src/chunking/stream.rs
+//
+// Потоковый чанкинг больших файлов (логи, дампы книг): текст читается из
+// AsyncRead кусками, каждый кусок режется по границе блоков и чанкуется
+// отдельно, так что в памяти не бывает ни всего файла, ни всех чанков.
+
+use std::collections::VecDeque;
+
+use anyhow::Result;
+use futures::Stream;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::{
+    block_end, chunk_id, closes_fence, make_chunks_with_headings, parse_blocks, parse_blocks_from, parse_front_matter,
+    skip_fence_body, Block, BlockKind, Chunk, ChunkingConfig, DocumentMeta, TokenCounter, RE_CODE_FENCE_OPEN,
+};
+use super::position::{span_pos, LineIndex, TextPos};
+
+const READ_BUF_BYTES: usize = 64 * 1024;
+const MIN_PIECE_BYTES: usize = 1024 * 1024;
+
+/// Code fence, не закрытый на границе куска: длина забора и язык.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct OpenFence {
+    ticks: usize,
+    lang: Option<String>,
+}
+
+/// Чанкер поверх `AsyncRead`. Спаны чанков — абсолютные байтовые смещения в
+/// исходном файле (битые UTF-8 последовательности декодируются в U+FFFD, но
+/// спаны пересчитываются обратно), id считаются от этих спанов. Стек заголовков переносится между кусками;
+/// перекрытие через границу куска не делается (она всегда между блоками,
+/// кроме code fence длиннее куска — тогда следующий кусок начинается внутри него).
+pub struct StreamingChunker<R> {
+    reader: R,
+    doc_id: String,
+    cfg: ChunkingConfig,
+    tc: TokenCounter,
+    piece_bytes: usize,
+    buf: Vec<u8>,
+    /// Незавершённая UTF-8 последовательность с конца прошлого чтения
+    carry: Vec<u8>,
+    pending: String,
+    /// Абсолютное смещение `pending[0]` в декодированном тексте
+    base: usize,
+    /// Замены U+FFFD: (смещение в тексте сразу после замены, накопленная
+    /// разница «байты файла − байты текста»); хранится только нужный хвост
+    fixups: Vec<(usize, isize)>,
+    base_pos: TextPos,
+    heading_stack: Vec<(usize, String)>,
+    /// Code fence, внутри которого начинается следующий кусок
+    fence: Option<OpenFence>,
+    front_matter: Option<DocumentMeta>,
+    ready: VecDeque<Chunk>,
+    eof: bool,
+}
+
+impl<R: AsyncRead + Unpin> StreamingChunker<R> {
+    pub fn new(doc_id: &str, reader: R, cfg: &ChunkingConfig, tc: &TokenCounter) -> Self {
+        Self {
+            reader,
+            doc_id: doc_id.to_string(),
+            cfg: cfg.clone(),
+            tc: tc.clone(),
+            // A piece holds many chunks, so cuts between pieces are rare
+            piece_bytes: (cfg.hard_max_bytes * 16).max(MIN_PIECE_BYTES),
+            buf: vec![0; READ_BUF_BYTES],
+            carry: Vec::new(),
+            pending: String::new(),
+            base: 0,
+            fixups: Vec::new(),
+            base_pos: TextPos::START,
+            heading_stack: Vec::new(),
+            fence: None,
+            front_matter: None,
+            ready: VecDeque::new(),
+            eof: false,
+        }
+    }
+
+    /// Front matter файла; известен после первого прочитанного чанка.
+    pub fn front_matter(&self) -> Option<&DocumentMeta> {
+        self.front_matter.as_ref()
+    }
+
+    /// Следующий чанк или `None` в конце файла.
+    pub async fn next_chunk(&mut self) -> Result<Option<Chunk>> {
+        self.fill().await?;
+        Ok(self.ready.pop_front())
+    }
+
+    /// До `max` следующих чанков; пустой вектор — конец файла.
+    pub async fn next_batch(&mut self, max: usize) -> Result<Vec<Chunk>> {
+        let mut batch = Vec::with_capacity(max);
+        while batch.len() < max {
+            match self.next_chunk().await? {
+                Some(chunk) => batch.push(chunk),
+                None => break,
+            }
+        }
+        Ok(batch)
+    }
+
+    /// Чанки как `Stream`.
+    pub fn into_stream(self) -> impl Stream<Item = Result<Chunk>> {
+        futures::stream::try_unfold(self, |mut chunker| async move {
+            Ok(chunker.next_chunk().await?.map(|chunk| (chunk, chunker)))
+        })
+    }
+
+    async fn fill(&mut self) -> Result<()> {
+        while self.ready.is_empty() && !(self.eof && self.pending.is_empty()) {
+            if !self.eof && self.pending.len() < self.piece_bytes {
+                let n = self.reader.read(&mut self.buf).await?;
+                if n == 0 {
+                    self.eof = true;
+                    // A truncated sequence at the very end is one U+FFFD
+                    let tail = std::mem::take(&mut self.carry);
+                    if !tail.is_empty() {
+                        self.push_replacement(tail.len());
+                    }
+                } else {
+                    let bytes = self.buf[..n].to_vec();
+                    self.push_bytes(&bytes);
+                }
+                continue;
+            }
+            let (cut, fence) = if self.eof {
+                (self.pending.len(), None)
+            } else {
+                safe_cut(&self.pending, self.piece_bytes, self.fence.as_ref())
+            };
+            self.chunk_piece(cut);
+            self.fence = fence;
+        }
+        Ok(())
+    }
+
+    /// Lossy-декодирование с переносом обрезанного на границе чтения символа.
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        let carry = std::mem::take(&mut self.carry);
+        let mut carry = [carry.as_slice(), bytes].concat();
+        let mut rest: &[u8] = &carry;
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(s) => {
+                    self.pending.push_str(s);
+                    rest = &[];
+                    break;
+                }
+                Err(e) => {
+                    let (valid, tail) = rest.split_at(e.valid_up_to());
+                    self.pending.push_str(std::str::from_utf8(valid).unwrap_or_default());
+                    match e.error_len() {
+                        Some(bad) => {
+                            self.push_replacement(bad);
+                            rest = &tail[bad..];
+                        }
+                        // Incomplete sequence at the end: wait for more bytes
+                        None => {
+                            rest = tail;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        let keep = carry.len() - rest.len();
+        carry.drain(..keep);
+        self.carry = carry;
+    }
+
+    /// U+FFFD вместо `bad` байт файла.
+    fn push_replacement(&mut self, bad: usize) {
+        self.pending.push(char::REPLACEMENT_CHARACTER);
+        let delta = self.fixups.last().map_or(0, |f| f.1) + bad as isize - char::REPLACEMENT_CHARACTER.len_utf8() as isize;
+        self.fixups.push((self.base + self.pending.len(), delta));
+    }
+
+    /// Абсолютное смещение в тексте → смещение в байтах файла.
+    fn to_source(&self, offset: usize) -> usize {
+        let i = self.fixups.partition_point(|f| f.0 <= offset);
+        let delta = i.checked_sub(1).map_or(0, |i| self.fixups[i].1);
+        (offset as isize + delta) as usize
+    }
+
+    fn chunk_piece(&mut self, cut: usize) {
+        let rest = self.pending.split_off(cut);
+        let piece = std::mem::replace(&mut self.pending, rest);
+
+        let blocks = match &self.fence {
+            Some(fence) => parse_blocks_in_fence(&piece, fence),
+            None if self.base == 0 => {
+                self.front_matter = parse_front_matter(&piece).map(|(_, meta)| meta);
+                parse_blocks(&piece)
+            }
+            None => parse_blocks_from(&piece, 0),
+        };
+        let chunks = make_chunks_with_headings(
+            &self.doc_id,
+            &piece,
+            &blocks,
+            &self.cfg,
+            &self.tc,
+            &mut self.heading_stack,
+        );
+        let index = LineIndex::new(&piece);
+        for mut chunk in chunks {
+            chunk.pos = span_pos(
+                self.base_pos.advance(index.pos(chunk.start)),
+                self.base_pos.advance(index.pos(chunk.end)),
+            );
+            chunk.start = self.to_source(self.base + chunk.start);
+            chunk.end = self.to_source(self.base + chunk.end);
+            chunk.id = chunk_id(&self.doc_id, chunk.start, chunk.end, &chunk.text);
+            self.ready.push_back(chunk);
+        }
+        self.base += piece.len();
+        self.base_pos = self.base_pos.advance(index.pos(piece.len()));
+        // Earlier fixups only matter through the last one before the new base
+        let passed = self.fixups.partition_point(|f| f.0 <= self.base);
+        self.fixups.drain(..passed.saturating_sub(1));
+    }
+}
+
+/// Блоки куска, начинающегося внутри `fence`: до закрывающего забора —
+/// продолжение кода (режется через `code_spans` как обычный fence), дальше
+/// обычный разбор.
+fn parse_blocks_in_fence(piece: &str, fence: &OpenFence) -> Vec<Block> {
+    let end = skip_fence_body(piece, 0, fence.ticks);
+    let mut blocks = vec![Block {
+        kind: BlockKind::CodeFence,
+        start: 0,
+        end: block_end(piece.as_bytes(), 0, end),
+        lang: fence.lang.clone(),
+    }];
+    blocks.extend(parse_blocks_from(piece, end));
+    blocks
+}
+
+/// Где отрезать кусок не длиннее `limit`: после последней пустой строки вне
+/// code fence, иначе после последней строки вне fence, иначе после любой строки,
+/// иначе по границе символа (файл без переводов строк). `fence` — забор,
+/// открытый в начале текста; вместе с позицией возвращается забор, открытый
+/// в месте разреза.
+fn safe_cut(text: &str, limit: usize, fence: Option<&OpenFence>) -> (usize, Option<OpenFence>) {
+    let mut fence = fence.cloned();
+    let (mut blank_cut, mut line_cut, mut any_cut) = (0, 0, 0);
+    let mut pos = 0;
+    for line in text.split_inclusive('\n') {
+        let end = pos + line.len();
+        if end > limit || !line.ends_with('\n') {
+            break;
+        }
+        let content = line.trim_end_matches(['\n', '\r']);
+        if let Some(open) = &fence {
+            if closes_fence(content, open.ticks) {
+                fence = None;
+            }
+        } else if let Some(caps) = RE_CODE_FENCE_OPEN.captures(content) {
+            fence = Some(OpenFence {
+                ticks: caps[1].len(),
+                lang: caps.get(2).map(|m| m.as_str().to_ascii_lowercase()),
+            });
+        }
+        if fence.is_none() {
+            line_cut = end;
+            if content.trim().is_empty() {
+                blank_cut = end;
+            }
+        }
+        any_cut = end;
+        pos = end;
+    }
+    // Blank and line cuts are outside fences; the loop stopped right at any_cut
+    if blank_cut > 0 {
+        return (blank_cut, None);
+    }
+    if line_cut > 0 {
+        return (line_cut, None);
+    }
+    if any_cut > 0 {
+        return (any_cut, fence);
+    }
+    let mut cut = limit.min(text.len());
+    while !text.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    (cut.max(text.chars().next().map_or(0, char::len_utf8)), fence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+
+    fn sample() -> String {
+        let mut doc = String::from("---\ntitle: Big dump\n---\n# Book\n\n");
+        for i in 0..40 {
+            doc.push_str(&format!("## Part {i}\n\n"));
+            doc.push_str("Строка лога с кириллицей и достаточно длинным текстом. ".repeat(6).trim_end());
+            doc.push_str("\n\n```rust\nfn main() {\n\n    println!(\"hi\");\n}\n```\n\n");
+        }
+        doc
+    }
+
+    #[tokio::test]
+    async fn test_stream_spans_and_headings() {
+        let doc = sample();
+        let cfg = ChunkingConfig::default();
+        let tc = TokenCounter::approx(cfg.approx_chars_per_token);
+        let mut chunker = StreamingChunker::new("doc::1", doc.as_bytes(), &cfg, &tc);
+        chunker.piece_bytes = 700;
+
+        let mut chunks = Vec::new();
+        loop {
+            let batch = chunker.next_batch(3).await.unwrap();
+            if batch.is_empty() {
+                break;
+            }
+            assert!(batch.len() <= 3);
+            chunks.extend(batch);
+        }
+        assert_eq!(chunker.front_matter().unwrap().title.as_deref(), Some("Big dump"));
+
+        let mut expected = chunks.clone();
+        super::super::locate_chunks(&doc, &mut expected);
+        for (c, e) in chunks.iter().zip(&expected) {
+            assert_eq!(&doc[c.start..c.end], c.text);
+            assert_eq!(c.pos, e.pos);
+            // Fences are never cut between pieces
+            assert_eq!(c.text.matches("```").count() % 2, 0, "{}", c.text);
+        }
+        let last = chunks.last().unwrap();
+        assert_eq!(last.headings, vec!["Book", "Part 39"]);
+        assert_eq!(chunks.iter().filter(|c| c.text.contains("println")).count(), 40);
+    }
+
+    #[tokio::test]
+    async fn test_stream_matches_in_memory_for_small_input() {
+        let doc = "# A\n\nFirst paragraph of text here.\n\n## B\n\nSecond paragraph of text.";
+        let cfg = ChunkingConfig::default();
+        let tc = TokenCounter::approx(cfg.approx_chars_per_token);
+        let streamed: Vec<Chunk> = StreamingChunker::new("doc::1", doc.as_bytes(), &cfg, &tc)
+            .into_stream()
+            .try_collect()
+            .await
+            .unwrap();
+        let in_memory = super::super::chunk_document_with_counter("doc::1", doc, &cfg, &tc);
+        assert_eq!(
+            streamed.iter().map(|c| &c.id).collect::<Vec<_>>(),
+            in_memory.iter().map(|c| &c.id).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_safe_cut_skips_longer_fences() {
+        let text = "intro\n\n````md\nA\n\n```sh\nls\n```\n\nB\n````\n\nafter\n";
+        // A shorter fence inside does not close the block, so no cut before its end
+        assert_eq!(safe_cut(text, text.find("B\n").unwrap(), None), ("intro\n\n".len(), None));
+        assert_eq!(safe_cut(text, text.len(), None), (text.find("after").unwrap(), None));
+    }
+
+    #[tokio::test]
+    async fn test_fence_longer_than_piece() {
+        let mut doc = String::from("# Doc\n\n## Code\n\n```python\n");
+        for i in 0..40 {
+            doc.push_str(&format!("# step {i}\ndef f{i}(x):\n    return x + {i}\n\n"));
+        }
+        doc.push_str("```\n\n## After\n\nProse after the long fence is a paragraph.\n\n- and a list item here\n");
+        let cfg = ChunkingConfig::default();
+        let tc = TokenCounter::approx(cfg.approx_chars_per_token);
+        let mut chunker = StreamingChunker::new("doc::1", doc.as_bytes(), &cfg, &tc);
+        chunker.piece_bytes = 300;
+        let chunks: Vec<Chunk> = chunker.into_stream().try_collect().await.unwrap();
+
+        for c in &chunks {
+            assert_eq!(&doc[c.start..c.end], c.text);
+            if c.text.contains("def f") {
+                // Comments inside the fence are not headers
+                assert_eq!(c.kind_summary, vec![BlockKind::CodeFence], "{}", c.text);
+                assert_eq!(c.lang.as_deref(), Some("python"));
+                assert_eq!(c.headings, vec!["Doc", "Code"]);
+            }
+        }
+        assert!(chunks.iter().filter(|c| c.text.contains("def f")).count() > 1);
+        let prose = chunks.iter().find(|c| c.text.contains("Prose after")).unwrap();
+        assert!(!prose.kind_summary.contains(&BlockKind::CodeFence));
+        assert!(prose.kind_summary.contains(&BlockKind::Paragraph));
+        assert_eq!(prose.headings, vec!["Doc", "After"]);
+        let last = chunks.last().unwrap();
+        assert!(last.kind_summary.contains(&BlockKind::List));
+        assert_eq!(last.headings, vec!["Doc", "After"]);
+    }
+
+    #[test]
+    fn test_utf8_split_across_reads() {
+        let cfg = ChunkingConfig::default();
+        let tc = TokenCounter::approx(cfg.approx_chars_per_token);
+        let mut chunker = StreamingChunker::new("doc::1", &b""[..], &cfg, &tc);
+        let bytes = "привет".as_bytes();
+        chunker.push_bytes(&bytes[..3]);
+        chunker.push_bytes(&bytes[3..]);
+        chunker.push_bytes(b"\xff!");
+        assert_eq!(chunker.pending, "привет\u{FFFD}!");
+        assert!(chunker.carry.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_spans_are_source_bytes_with_invalid_utf8() {
+        let mut src = b"# Log\n\nbad \xff\xfe bytes here\n\n".to_vec();
+        for i in 0..30 {
+            src.extend_from_slice(format!("Entry {i} ").as_bytes());
+            src.push(0xc3);
+            src.extend_from_slice(b" with text after it.\n\n\x80");
+            src.extend_from_slice(b"\n\n");
+        }
+        src.extend_from_slice(b"tail \xe2\x82");
+        let cfg = ChunkingConfig::default();
+        let tc = TokenCounter::approx(cfg.approx_chars_per_token);
+        let mut chunker = StreamingChunker::new("doc::1", src.as_slice(), &cfg, &tc);
+        chunker.piece_bytes = 200;
+        let chunks: Vec<Chunk> = chunker.into_stream().try_collect().await.unwrap();
+        assert!(chunks.len() > 1);
+        for c in &chunks {
+            assert_eq!(String::from_utf8_lossy(&src[c.start..c.end]), c.text);
+        }
+        assert_eq!(chunks.last().unwrap().end, src.len());
+    }
+}
@@ -4,19 +4,73 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::chunking::ChunkingStrategy;
+use crate::extract::ArchiveLimits;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RagConfig {
     pub chunking: ChunkingConfig,
     pub embedder: EmbedderConfig,
     pub qdrant: QdrantConfig,
+    #[serde(default)]
+    pub records: RecordMapping,
+    #[serde(default)]
+    pub notebook: NotebookConfig,
+    #[serde(default)]
+    pub archive: ArchiveLimits,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChunkingConfig {
     pub max_tokens: usize,
     pub overlap_tokens: usize,
+    #[serde(default)]
+    pub overlap_across_blocks: bool,
     pub approx_chars_per_token: f32,
     pub hard_max_bytes: usize,
+    #[serde(default = "default_true")]
+    pub embed_headings: bool,
+    /// Чанкер: blocks | sentence-window | token-window | semantic
+    #[serde(default, alias = "chunker")]
+    pub strategy: ChunkingStrategy,
+    #[serde(default = "default_breakpoint_percentile")]
+    pub semantic_breakpoint_percentile: f32,
+    #[serde(default = "default_buffer_size")]
+    pub semantic_buffer_size: usize,
+}
+
+/// Индексация CSV/JSON/JSONL по записям: каждая запись — отдельный документ.
+/// Включается явно (`enabled`) или любым заданным полем маппинга, иначе такие
+/// файлы индексируются как обычный текст.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RecordMapping {
+    /// Индексировать по записям и без маппинга полей
+    #[serde(default)]
+    pub enabled: bool,
+    /// Поля, из которых собирается текст для эмбеддинга (пусто — все, кроме id и метаданных)
+    #[serde(default)]
+    pub text_fields: Vec<String>,
+    /// Поля, которые попадают в payload как метаданные
+    #[serde(default)]
+    pub meta_fields: Vec<String>,
+    /// Поле со стабильным id записи (иначе id — номер записи в файле)
+    #[serde(default)]
+    pub id_field: Option<String>,
+}
+
+impl RecordMapping {
+    /// Включена ли индексация по записям.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled || !self.text_fields.is_empty() || !self.meta_fields.is_empty() || self.id_field.is_some()
+    }
+}
+
+/// Индексация Jupyter-ноутбуков (.ipynb).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NotebookConfig {
+    /// Добавлять текстовые выводы ячеек (stdout, результат, ошибка) после кода
+    #[serde(default)]
+    pub include_outputs: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,13 +86,46 @@ pub struct QdrantConfig {
     pub collection: String,
 }
 
+fn default_true() -> bool {
+    true
+}
+
+fn default_breakpoint_percentile() -> f32 {
+    95.0
+}
+
+fn default_buffer_size() -> usize {
+    1
+}
+
 impl Default for ChunkingConfig {
     fn default() -> Self {
         Self {
             max_tokens: 350,
             overlap_tokens: 60,
+            overlap_across_blocks: false,
             approx_chars_per_token: 4.0,
             hard_max_bytes: 96 * 1024,
+            embed_headings: true,
+            strategy: ChunkingStrategy::Blocks,
+            semantic_breakpoint_percentile: default_breakpoint_percentile(),
+            semantic_buffer_size: default_buffer_size(),
+        }
+    }
+}
+
+impl From<ChunkingConfig> for crate::chunking::ChunkingConfig {
+    fn from(c: ChunkingConfig) -> Self {
+        Self {
+            max_tokens: c.max_tokens,
+            overlap_tokens: c.overlap_tokens,
+            overlap_across_blocks: c.overlap_across_blocks,
+            approx_chars_per_token: c.approx_chars_per_token,
+            hard_max_bytes: c.hard_max_bytes,
+            embed_headings: c.embed_headings,
+            strategy: c.strategy,
+            semantic_breakpoint_percentile: c.semantic_breakpoint_percentile,
+            semantic_buffer_size: c.semantic_buffer_size,
         }
     }
 }
@@ -68,6 +155,9 @@ impl Default for RagConfig {
             chunking: ChunkingConfig::default(),
             embedder: EmbedderConfig::default(),
             qdrant: QdrantConfig::default(),
+            records: RecordMapping::default(),
+            notebook: NotebookConfig::default(),
+            archive: ArchiveLimits::default(),
         }
     }
 }
@@ -0,0 +1,206 @@
+// file: src/extract/archive.rs
+//
+// Архивы zip/tar/tar.gz: распаковка в память с лимитами на число файлов и
+// размер (защита от zip-бомб). Каждый файл потом индексируется как отдельный
+// документ с `source_id` вида `archive.zip!/path/inside`.
+
+use std::io::{Cursor, Read};
+use std::path::{Component, Path};
+
+use anyhow::{anyhow, Context, Result};
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+/// Лимиты распаковки; превышение числа файлов или общего размера — ошибка
+/// для всего архива, слишком большой отдельный файл просто пропускается.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArchiveLimits {
+    pub max_entries: usize,
+    pub max_entry_bytes: u64,
+    pub max_total_bytes: u64,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 10_000,
+            max_entry_bytes: 64 * 1024 * 1024,
+            max_total_bytes: 512 * 1024 * 1024,
+        }
+    }
+}
+
+/// Файл из архива: путь внутри (без `..` и ведущего `/`) и содержимое.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    pub path: String,
+    pub bytes: Vec<u8>,
+    /// Причина, по которой файл не распакован (тогда `bytes` пустой)
+    pub skipped: Option<String>,
+}
+
+pub fn unpack_archive(kind: ArchiveKind, bytes: &[u8], limits: &ArchiveLimits) -> Result<Vec<ArchiveEntry>> {
+    let mut sink = EntrySink {
+        limits,
+        entries: Vec::new(),
+        total: 0,
+    };
+    match kind {
+        ArchiveKind::Zip => {
+            let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).context("not a valid zip archive")?;
+            for i in 0..zip.len() {
+                let file = zip.by_index(i)?;
+                if file.is_dir() {
+                    continue;
+                }
+                let Some(path) = file.enclosed_name() else { continue };
+                let size = file.size();
+                sink.push(&path, size, file)?;
+            }
+        }
+        ArchiveKind::Tar => unpack_tar(Cursor::new(bytes), &mut sink)?,
+        ArchiveKind::TarGz => unpack_tar(GzDecoder::new(Cursor::new(bytes)), &mut sink)?,
+    }
+    Ok(sink.entries)
+}
+
+fn unpack_tar<R: Read>(reader: R, sink: &mut EntrySink) -> Result<()> {
+    let mut tar = tar::Archive::new(reader);
+    for entry in tar.entries().context("not a valid tar archive")? {
+        let entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        let size = entry.header().size()?;
+        sink.push(&path, size, entry)?;
+    }
+    Ok(())
+}
+
+struct EntrySink<'a> {
+    limits: &'a ArchiveLimits,
+    entries: Vec<ArchiveEntry>,
+    total: u64,
+}
+
+impl EntrySink<'_> {
+    fn push(&mut self, path: &Path, declared_size: u64, reader: impl Read) -> Result<()> {
+        let Some(path) = clean_path(path) else { return Ok(()) };
+        if self.entries.len() >= self.limits.max_entries {
+            return Err(anyhow!("archive has more than {} files", self.limits.max_entries));
+        }
+        if declared_size > self.limits.max_entry_bytes {
+            self.entries.push(ArchiveEntry {
+                path,
+                bytes: Vec::new(),
+                skipped: Some(format!("file is larger than {} bytes", self.limits.max_entry_bytes)),
+            });
+            return Ok(());
+        }
+
+        // Declared sizes can lie: never read more than the limits allow
+        let mut bytes = Vec::new();
+        reader.take(self.limits.max_entry_bytes + 1).read_to_end(&mut bytes)?;
+        self.total += bytes.len() as u64;
+        if self.total > self.limits.max_total_bytes {
+            return Err(anyhow!("archive unpacks to more than {} bytes", self.limits.max_total_bytes));
+        }
+        let skipped = (bytes.len() as u64 > self.limits.max_entry_bytes)
+            .then(|| format!("file is larger than {} bytes", self.limits.max_entry_bytes));
+        if skipped.is_some() {
+            bytes.clear();
+        }
+        self.entries.push(ArchiveEntry { path, bytes, skipped });
+        Ok(())
+    }
+}
+
+/// Относительный путь через `/`; `None` — служебный мусор (`__MACOSX/`, `.DS_Store`,
+/// скрытые файлы) или путь, выходящий за пределы архива.
+fn clean_path(path: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+    for c in path.components() {
+        match c {
+            Component::Normal(p) => parts.push(p.to_str()?.to_string()),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    let hidden = parts.iter().any(|p| p.starts_with('.') || p == "__MACOSX");
+    (!parts.is_empty() && !hidden).then(|| parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::{sniff_content, test_zip, ContentType};
+
+    fn make_tar_gz(parts: &[(&str, &[u8])]) -> Vec<u8> {
+        let gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut tar = tar::Builder::new(gz);
+        for (name, content) in parts {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, *content).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_unpack_zip_and_tar_gz() {
+        let zip = test_zip(&[
+            ("docs/a.md", b"# A"),
+            ("docs/.DS_Store", b"junk"),
+            ("__MACOSX/docs/._a.md", b"junk"),
+            ("big.txt", &[b'x'; 32]),
+        ]);
+        assert_eq!(sniff_content("docs.zip", &zip), ContentType::Archive(ArchiveKind::Zip));
+        assert_eq!(sniff_content("upload", &zip), ContentType::Archive(ArchiveKind::Zip));
+        // A zip without DOCX parts is only a DOCX by its name
+        assert_eq!(sniff_content("spec.docx", &zip), ContentType::Docx);
+
+        let limits = ArchiveLimits {
+            max_entry_bytes: 16,
+            ..Default::default()
+        };
+        let entries = unpack_archive(ArchiveKind::Zip, &zip, &limits).unwrap();
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["docs/a.md", "big.txt"]);
+        assert_eq!(entries[0].bytes, b"# A");
+        assert!(entries[1].skipped.is_some());
+
+        let tgz = make_tar_gz(&[("notes/b.txt", b"hello"), ("c.csv", b"a,b\n1,2\n")]);
+        assert_eq!(sniff_content("bundle.bin", &tgz), ContentType::Archive(ArchiveKind::TarGz));
+        let entries = unpack_archive(ArchiveKind::TarGz, &tgz, &ArchiveLimits::default()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, "notes/b.txt");
+        assert_eq!(entries[0].bytes, b"hello");
+    }
+
+    #[test]
+    fn test_limits() {
+        let zip = test_zip(&[("a.txt", b"aaaa"), ("b.txt", b"bbbb"), ("c.txt", b"cccc")]);
+        let too_many = ArchiveLimits {
+            max_entries: 2,
+            ..Default::default()
+        };
+        assert!(unpack_archive(ArchiveKind::Zip, &zip, &too_many).is_err());
+        let too_big = ArchiveLimits {
+            max_total_bytes: 10,
+            ..Default::default()
+        };
+        assert!(unpack_archive(ArchiveKind::Zip, &zip, &too_big).is_err());
+        assert_eq!(clean_path(Path::new("../etc/passwd")), None);
+    }
+}
@@ -0,0 +1,300 @@
+// file: src/extract/epub.rs
+//
+// EPUB → markdown-подобный текст: главы в порядке spine, каждая через
+// `extract_html`. Название книги и автор — в метаданные, номер и название
+// главы — `Region` с полями `chapter`/`chapter_end`/`chapter_title`.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context, Result};
+use roxmltree::{Document, Node};
+
+use super::office::{open_zip, read_part};
+use super::{extract_html, Extracted, Region};
+
+pub fn extract_epub(bytes: &[u8]) -> Result<Extracted> {
+    let mut zip = open_zip(bytes)?;
+    let container = read_part(&mut zip, "META-INF/container.xml")?
+        .ok_or_else(|| anyhow!("META-INF/container.xml is missing: not an EPUB file"))?;
+    let opf_path = Document::parse(&container)
+        .context("malformed META-INF/container.xml")?
+        .descendants()
+        .find(|n| n.tag_name().name() == "rootfile")
+        .and_then(|n| n.attribute("full-path"))
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("EPUB container has no rootfile"))?;
+    let opf_xml = read_part(&mut zip, &opf_path)?
+        .ok_or_else(|| anyhow!("package document {} is missing", opf_path))?;
+    let opf = Document::parse(&opf_xml).context("malformed EPUB package document")?;
+    let base = parent_dir(&opf_path);
+
+    let mut out = Extracted::default();
+    if let Some(metadata) = opf.descendants().find(|n| local(*n, "metadata")) {
+        let dc = |name: &str| {
+            metadata
+                .children()
+                .find(|n| local(*n, name))
+                .and_then(|n| n.text())
+                .map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|t| !t.is_empty())
+        };
+        out.meta.title = dc("title");
+        out.meta.author = dc("creator");
+        if let Some(lang) = dc("language") {
+            out.meta.extra.insert("lang".into(), serde_json::Value::from(lang));
+        }
+        if let Some(publisher) = dc("publisher") {
+            out.meta.extra.insert("publisher".into(), serde_json::Value::from(publisher));
+        }
+    }
+
+    // Manifest: id → (path in the archive, media type, properties)
+    let manifest: HashMap<&str, (String, &str, &str)> = opf
+        .descendants()
+        .filter(|n| local(*n, "item"))
+        .filter_map(|n| {
+            let href = n.attribute("href")?;
+            Some((
+                n.attribute("id")?,
+                (
+                    resolve(&base, href),
+                    n.attribute("media-type").unwrap_or(""),
+                    n.attribute("properties").unwrap_or(""),
+                ),
+            ))
+        })
+        .collect();
+    let toc = table_of_contents(&mut zip, &opf, &manifest)?;
+
+    let spine: Vec<&str> = opf
+        .descendants()
+        .filter(|n| local(*n, "itemref"))
+        .filter(|n| n.attribute("linear") != Some("no"))
+        .filter_map(|n| n.attribute("idref"))
+        .collect();
+    if spine.is_empty() {
+        return Err(anyhow!("EPUB spine is empty"));
+    }
+
+    let mut number = 0;
+    for idref in spine {
+        let Some((path, media, _)) = manifest.get(idref) else { continue };
+        if !media.contains("html") {
+            continue;
+        }
+        let Some(xhtml) = read_part(&mut zip, path)? else {
+            eprintln!("⚠️  WARN: EPUB spine item {} is missing", path);
+            continue;
+        };
+        let chapter = extract_html(&xhtml);
+        let text = chapter.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        number += 1;
+        let title = toc
+            .get(path.as_str())
+            .cloned()
+            .or_else(|| first_heading(text))
+            .or(chapter.meta.title);
+
+        if !out.text.is_empty() {
+            out.text.push_str("\n\n");
+        }
+        let start = out.text.len();
+        out.text.push_str(text);
+        let mut region = Region {
+            start,
+            end: out.text.len(),
+            ..Default::default()
+        };
+        region.fields.insert("chapter".into(), serde_json::Value::from(number));
+        region.fields.insert("chapter_end".into(), serde_json::Value::from(number));
+        if let Some(title) = title {
+            region.fields.insert("chapter_title".into(), serde_json::Value::from(title));
+        }
+        out.regions.push(region);
+    }
+    if out.text.is_empty() {
+        return Err(anyhow!("EPUB has no readable chapters"));
+    }
+    Ok(out)
+}
+
+/// Путь файла главы → название из оглавления: EPUB 3 nav, иначе NCX (EPUB 2).
+/// Для файла берётся первая ссылка на него (якоря `#...` отбрасываются).
+fn table_of_contents(
+    zip: &mut super::office::Archive,
+    opf: &Document,
+    manifest: &HashMap<&str, (String, &str, &str)>,
+) -> Result<HashMap<String, String>> {
+    let nav = manifest
+        .values()
+        .find(|(_, _, props)| props.split_whitespace().any(|p| p == "nav"));
+    let ncx = opf
+        .descendants()
+        .find(|n| local(*n, "spine"))
+        .and_then(|s| s.attribute("toc"))
+        .and_then(|id| manifest.get(id))
+        .or_else(|| manifest.values().find(|(_, media, _)| *media == "application/x-dtbncx+xml"));
+
+    let mut toc = HashMap::new();
+    for (path, is_nav) in nav.map(|n| (n, true)).into_iter().chain(ncx.map(|n| (n, false))) {
+        let Some(xml) = read_part(zip, &path.0)? else { continue };
+        // Navigation documents may use HTML entities roxmltree rejects; the spine still works
+        let Ok(doc) = Document::parse(&xml) else { continue };
+        let base = parent_dir(&path.0);
+        let entries: Vec<(String, String)> = if is_nav {
+            doc.descendants()
+                .filter(|n| local(*n, "nav"))
+                .filter(|n| n.attributes().any(|a| a.name() == "type" && a.value().contains("toc")))
+                .flat_map(|nav| nav.descendants().filter(|n| local(*n, "a")))
+                .filter_map(|a| Some((a.attribute("href")?.to_string(), node_text(a))))
+                .collect()
+        } else {
+            doc.descendants()
+                .filter(|n| local(*n, "navPoint"))
+                .filter_map(|p| {
+                    let label = p.children().find(|c| local(*c, "navLabel")).map(node_text)?;
+                    let src = p.children().find(|c| local(*c, "content"))?.attribute("src")?;
+                    Some((src.to_string(), label))
+                })
+                .collect()
+        };
+        for (href, label) in entries {
+            let file = href.split('#').next().unwrap_or("");
+            if !label.is_empty() && !file.is_empty() {
+                toc.entry(resolve(&base, file)).or_insert(label);
+            }
+        }
+        if !toc.is_empty() {
+            break;
+        }
+    }
+    Ok(toc)
+}
+
+fn local(node: Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name
+}
+
+fn node_text(node: Node) -> String {
+    let text: String = node.descendants().filter(|d| d.is_text()).filter_map(|d| d.text()).collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Первый markdown-заголовок главы (если оглавления нет).
+fn first_heading(text: &str) -> Option<String> {
+    text.lines()
+        .find(|l| l.starts_with('#'))
+        .map(|l| l.trim_start_matches('#').trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+fn parent_dir(path: &str) -> String {
+    path.rsplit_once('/').map(|(dir, _)| dir.to_string()).unwrap_or_default()
+}
+
+/// Путь внутри архива для `href` относительно каталога `base` (`..`, `%20`).
+fn resolve(base: &str, href: &str) -> String {
+    let mut parts: Vec<String> = base.split('/').filter(|p| !p.is_empty()).map(str::to_string).collect();
+    for seg in percent_decode(href).split('/') {
+        match seg {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            s => parts.push(s.to_string()),
+        }
+    }
+    parts.join("/")
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(b) = s.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::{region_fields, test_zip};
+
+    const OPF: &str = r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>Designing Data-Intensive Applications</dc:title>
+    <dc:creator>Martin Kleppmann</dc:creator><dc:language>en</dc:language>
+  </metadata>
+  <manifest>
+    <item id="c2" href="text/ch%202.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c1" href="text/ch1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="cover" href="cover.xhtml" media-type="application/xhtml+xml"/>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+  </manifest>
+  <spine><itemref idref="cover" linear="no"/><itemref idref="c1"/><itemref idref="c2"/></spine>
+</package>"#;
+
+    const NAV: &str = r#"<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops"><body>
+<nav epub:type="toc"><ol><li><a href="text/ch1.xhtml#start">1. Reliable, Scalable, and Maintainable</a></li></ol></nav>
+</body></html>"#;
+
+    #[test]
+    fn test_spine_order_and_chapters() {
+        let bytes = test_zip(&[
+            (
+                "META-INF/container.xml",
+                br#"<container><rootfiles><rootfile full-path="OEBPS/content.opf"/></rootfiles></container>"#,
+            ),
+            ("OEBPS/content.opf", OPF.as_bytes()),
+            ("OEBPS/nav.xhtml", NAV.as_bytes()),
+            ("OEBPS/cover.xhtml", b"<html><body><p>Cover</p></body></html>"),
+            (
+                "OEBPS/text/ch1.xhtml",
+                b"<html><body><h1>Reliable Systems</h1><p>Faults are not failures.</p></body></html>",
+            ),
+            (
+                "OEBPS/text/ch 2.xhtml",
+                b"<html><body><h1>Data Models</h1><p>Relational versus document.</p></body></html>",
+            ),
+        ]);
+        let ex = extract_epub(&bytes).unwrap();
+
+        assert_eq!(
+            ex.text,
+            "# Reliable Systems\n\nFaults are not failures.\n\n# Data Models\n\nRelational versus document."
+        );
+        assert_eq!(ex.meta.title.as_deref(), Some("Designing Data-Intensive Applications"));
+        assert_eq!(ex.meta.author.as_deref(), Some("Martin Kleppmann"));
+        assert_eq!(ex.meta.extra["lang"], "en");
+
+        assert_eq!(ex.regions.len(), 2);
+        assert_eq!(ex.regions[0].fields["chapter_title"], "1. Reliable, Scalable, and Maintainable");
+        // No TOC entry: falls back to the chapter's first heading
+        assert_eq!(ex.regions[1].fields["chapter_title"], "Data Models");
+
+        let at = ex.text.find("Relational").unwrap();
+        let fields = region_fields(&ex.regions, at, ex.text.len());
+        assert_eq!(fields["chapter"], 2);
+        assert_eq!(fields["chapter_end"], 2);
+    }
+
+    #[test]
+    fn test_resolve_paths() {
+        assert_eq!(resolve("OEBPS/text", "../images/a%20b.png"), "OEBPS/images/a b.png");
+        assert_eq!(resolve("", "./ch1.xhtml"), "ch1.xhtml");
+    }
+}
@@ -0,0 +1,416 @@
+// file: src/extract/html.rs
+//
+// HTML → markdown-подобный текст: выкидываем скрипты, навигацию, футеры и
+// прочий boilerplate, заголовки/списки/таблицы/код переводим в markdown,
+// чтобы `parse_blocks` видел структуру. <title> и lang — в метаданные.
+
+use ego_tree::NodeRef;
+use scraper::{ElementRef, Html, Node, Selector};
+
+use super::{code_fence, escape_marker, markdown_table, Extracted};
+
+/// Теги, которые никогда не несут полезного текста.
+const SKIP_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "iframe", "svg", "canvas", "form", "button",
+    "select", "input", "textarea", "nav", "aside", "footer", "head", "object", "embed", "menu",
+    "dialog",
+];
+
+/// id/классы навигации, рекламы и баннеров. Сравниваются целиком: часть
+/// составного имени (`share-price`, `menu-pricing`) — не признак boilerplate.
+const BOILERPLATE_HINTS: &[&str] = &[
+    "nav", "navbar", "navigation", "main-nav", "site-nav", "menu", "main-menu", "top-menu",
+    "sidebar", "footer", "site-footer", "page-footer", "cookie", "cookies", "cookie-banner",
+    "cookie-consent", "cookie-notice", "banner", "breadcrumb", "breadcrumbs", "advert",
+    "advertisement", "ads", "ad-banner", "share", "share-buttons", "social", "social-links",
+    "popup", "modal", "newsletter", "subscribe",
+];
+
+const SKIP_ROLES: &[&str] = &[
+    "navigation", "banner", "contentinfo", "complementary", "search", "menu", "menubar",
+];
+
+/// Блочные контейнеры: их содержимое обходится рекурсивно.
+const CONTAINER_TAGS: &[&str] = &[
+    "html", "body", "main", "article", "section", "div", "header", "figure", "figcaption", "dl",
+    "dt", "dd", "details", "summary", "address", "center", "fieldset", "hgroup",
+];
+
+/// Похоже ли содержимое на HTML-документ (для файлов без расширения).
+pub fn looks_like_html(text: &str) -> bool {
+    let head: String = text.trim_start_matches('\u{FEFF}').trim_start().chars().take(256).collect();
+    let head = head.to_ascii_lowercase();
+    head.starts_with("<!doctype html") || head.starts_with("<html")
+}
+
+/// Основной текст страницы в markdown-подобном виде + title/lang/author.
+pub fn extract_html(html: &str) -> Extracted {
+    let doc = Html::parse_document(html);
+    let mut out = Extracted::default();
+
+    out.meta.title = select_first(&doc, "title")
+        .map(|t| collapse(&t.text().collect::<String>()))
+        .filter(|t| !t.is_empty());
+    out.meta.author = select_first(&doc, r#"meta[name="author"]"#)
+        .and_then(|m| m.value().attr("content"))
+        .map(collapse)
+        .filter(|a| !a.is_empty());
+    if let Some(lang) = select_first(&doc, "html")
+        .and_then(|h| h.value().attr("lang"))
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+    {
+        out.meta.extra.insert("lang".into(), serde_json::Value::from(lang.to_string()));
+    }
+
+    // Content root: <main>/<article> when present, otherwise the whole body
+    let (root, page_level) = match select_first(&doc, r#"main, [role="main"]"#)
+        .or_else(|| select_first(&doc, "article"))
+    {
+        Some(r) => (r, false),
+        None => (select_first(&doc, "body").unwrap_or(doc.root_element()), true),
+    };
+
+    let mut w = Writer {
+        page_level,
+        ..Default::default()
+    };
+    w.walk(*root);
+    w.flush();
+    out.text = w.out;
+    out
+}
+
+fn select_first<'a>(doc: &'a Html, css: &str) -> Option<ElementRef<'a>> {
+    let sel = Selector::parse(css).ok()?;
+    doc.select(&sel).next()
+}
+
+#[derive(Default)]
+struct Writer {
+    out: String,
+    /// Inline text of the paragraph being collected
+    para: String,
+    /// Root is <body>: page-level <header> is boilerplate too
+    page_level: bool,
+}
+
+impl Writer {
+    fn push_block(&mut self, block: &str) {
+        self.flush();
+        let block = block.trim_end();
+        if block.trim().is_empty() {
+            return;
+        }
+        if !self.out.is_empty() {
+            self.out.push_str("\n\n");
+        }
+        self.out.push_str(block);
+    }
+
+    fn flush(&mut self) {
+        let para = std::mem::take(&mut self.para);
+        let text = para
+            .split('\n')
+            .map(collapse)
+            .filter(|l| !l.is_empty())
+            .map(|l| escape_marker(&l))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !text.is_empty() {
+            self.push_block(&text);
+        }
+    }
+
+    fn walk(&mut self, node: NodeRef<Node>) {
+        for child in node.children() {
+            match child.value() {
+                Node::Text(t) => push_text(&mut self.para, t),
+                Node::Element(e) => {
+                    if self.skipped(child) {
+                        continue;
+                    }
+                    match e.name() {
+                        h @ ("h1" | "h2" | "h3" | "h4" | "h5" | "h6") => {
+                            let level = h[1..].parse::<usize>().unwrap_or(1);
+                            let title = collapse(&inline(child));
+                            if !title.is_empty() {
+                                self.push_block(&format!("{} {}", "#".repeat(level), title));
+                            }
+                        }
+                        "p" => {
+                            self.flush();
+                            self.para = inline(child);
+                            self.flush();
+                        }
+                        "ul" | "ol" => {
+                            let mut lines = Vec::new();
+                            list(child, 0, &mut lines);
+                            self.push_block(&lines.join("\n"));
+                        }
+                        "table" => self.push_block(&table(child)),
+                        "pre" => self.push_block(&code_block(child)),
+                        "blockquote" => {
+                            let mut inner = Writer::default();
+                            inner.walk(child);
+                            inner.flush();
+                            let quoted: Vec<String> = inner
+                                .out
+                                .lines()
+                                .map(|l| if l.is_empty() { ">".to_string() } else { format!("> {}", l) })
+                                .collect();
+                            self.push_block(&quoted.join("\n"));
+                        }
+                        "br" => self.para.push('\n'),
+                        "hr" => self.flush(),
+                        name if CONTAINER_TAGS.contains(&name) => {
+                            self.flush();
+                            self.walk(child);
+                            self.flush();
+                        }
+                        _ => self.para.push_str(&inline(child)),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn skipped(&self, node: NodeRef<Node>) -> bool {
+        is_boilerplate(node) || (self.page_level && is_element(node, "header"))
+    }
+}
+
+fn is_element(node: NodeRef<Node>, name: &str) -> bool {
+    node.value().as_element().is_some_and(|e| e.name() == name)
+}
+
+fn is_boilerplate(node: NodeRef<Node>) -> bool {
+    let Some(e) = node.value().as_element() else {
+        return false;
+    };
+    if SKIP_TAGS.contains(&e.name()) {
+        return true;
+    }
+    if e.attr("role").is_some_and(|r| SKIP_ROLES.contains(&r)) {
+        return true;
+    }
+    if e.attr("hidden").is_some() || e.attr("aria-hidden") == Some("true") {
+        return true;
+    }
+    let marker = format!("{} {}", e.attr("id").unwrap_or(""), e.attr("class").unwrap_or(""))
+        .to_ascii_lowercase();
+    marker
+        .split_whitespace()
+        .any(|token| BOILERPLATE_HINTS.contains(&token))
+}
+
+/// Схлопывает пробельные последовательности в один пробел.
+fn collapse(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Текстовый узел в абзац: переводы строк исходника — просто пробелы,
+/// строки абзаца разделяет только `<br>`.
+fn push_text(s: &mut String, text: &str) {
+    s.extend(text.chars().map(|c| if c.is_whitespace() { ' ' } else { c }));
+}
+
+/// Текст элемента как одна строка абзаца; `<br>` — перевод строки, `<code>` — в бэктиках.
+fn inline(node: NodeRef<Node>) -> String {
+    let mut s = String::new();
+    inline_into(node, &mut s);
+    s
+}
+
+fn inline_into(node: NodeRef<Node>, s: &mut String) {
+    for child in node.children() {
+        match child.value() {
+            Node::Text(t) => push_text(s, t),
+            Node::Element(e) => {
+                if is_boilerplate(child) {
+                    continue;
+                }
+                match e.name() {
+                    "br" => s.push('\n'),
+                    "code" => {
+                        let code = collapse(&inline(child));
+                        if !code.is_empty() {
+                            s.push_str(&format!("`{}`", code));
+                        }
+                    }
+                    // Nested lists are rendered by `list`
+                    "ul" | "ol" => {}
+                    name => {
+                        let block = CONTAINER_TAGS.contains(&name) || name == "p";
+                        if block {
+                            s.push(' ');
+                        }
+                        inline_into(child, s);
+                        if block {
+                            s.push(' ');
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Пункты списка (`- ` / `1. `), вложенные списки с отступом в два пробела.
+fn list(node: NodeRef<Node>, depth: usize, lines: &mut Vec<String>) {
+    let ordered = is_element(node, "ol");
+    let start: usize = node
+        .value()
+        .as_element()
+        .and_then(|e| e.attr("start"))
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1);
+    let indent = "  ".repeat(depth);
+
+    for (i, li) in node.children().filter(|c| is_element(*c, "li")).enumerate() {
+        let text = collapse(&inline(li));
+        let marker = if ordered { format!("{}.", start + i) } else { "-".to_string() };
+        lines.push(format!("{}{} {}", indent, marker, text).trim_end().to_string());
+        for nested in li.descendants().filter(|d| is_element(*d, "ul") || is_element(*d, "ol")) {
+            // Only the nearest nested lists; deeper ones are handled recursively
+            let direct = nested
+                .ancestors()
+                .take_while(|a| a.id() != li.id())
+                .all(|a| !is_element(a, "ul") && !is_element(a, "ol"));
+            if direct {
+                list(nested, depth + 1, lines);
+            }
+        }
+    }
+}
+
+/// Таблица в markdown: первая строка — заголовок, ячейки без переводов строк и `|`.
+fn table(node: NodeRef<Node>) -> String {
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for tr in node.descendants().filter(|d| is_element(*d, "tr")) {
+        let cells: Vec<String> = tr
+            .children()
+            .filter(|c| is_element(*c, "td") || is_element(*c, "th"))
+            .map(|c| collapse(&inline(c)))
+            .collect();
+        if !cells.is_empty() {
+            rows.push(cells);
+        }
+    }
+    markdown_table(&rows)
+}
+
+/// `<pre>` → fenced code block; язык из `class="language-x"`/`lang-x`.
+fn code_block(node: NodeRef<Node>) -> String {
+    let text: String = node
+        .descendants()
+        .filter_map(|d| d.value().as_text().map(|t| t.to_string()))
+        .collect();
+    let lang = node
+        .descendants()
+        .filter_map(|d| d.value().as_element())
+        .filter_map(|e| e.attr("class"))
+        .flat_map(|c| c.split_whitespace())
+        .find_map(|c| c.strip_prefix("language-").or_else(|| c.strip_prefix("lang-")))
+        .filter(|l| l.chars().all(|c| c.is_ascii_alphanumeric() || "_+-".contains(c)))
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    code_fence(&lang, text.trim_matches('\n'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunking::{parse_blocks, BlockKind};
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html lang="ru"><head><title> Гайд по Rust </title>
+<meta name="author" content="Ferris"><script>var x = 1;</script></head>
+<body>
+  <header><a href="/">Home</a></header>
+  <nav class="top-menu"><ul><li>Docs</li><li>Blog</li></ul></nav>
+  <div class="cookie-banner">We use cookies</div>
+  <main>
+    <h1>Владение</h1>
+    <p>Каждое значение   имеет <b>владельца</b>, см. <code>Box&lt;T&gt;</code>.</p>
+    <ul><li>Move<ul><li>Copy types</li></ul></li><li>Borrow</li></ul>
+    <table><tr><th>Тип</th><th>Размер</th></tr><tr><td>u8</td><td>1</td></tr></table>
+    <pre><code class="language-rust">fn main() {
+    println!("hi");
+}</code></pre>
+  </main>
+  <footer>© 2024</footer>
+</body></html>"#;
+
+    #[test]
+    fn test_extract_structure_and_meta() {
+        let ex = extract_html(PAGE);
+        assert_eq!(ex.meta.title.as_deref(), Some("Гайд по Rust"));
+        assert_eq!(ex.meta.author.as_deref(), Some("Ferris"));
+        assert_eq!(ex.meta.extra["lang"], "ru");
+
+        for junk in ["var x", "Home", "Docs", "cookies", "©"] {
+            assert!(!ex.text.contains(junk), "{junk} leaked: {}", ex.text);
+        }
+        assert!(ex.text.contains("Каждое значение имеет владельца, см. `Box<T>`."));
+        assert!(ex.text.contains("- Move\n  - Copy types\n- Borrow"));
+
+        let kinds: Vec<BlockKind> = parse_blocks(&ex.text).iter().map(|b| b.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![BlockKind::Header, BlockKind::Paragraph, BlockKind::List, BlockKind::Table, BlockKind::CodeFence]
+        );
+        assert_eq!(parse_blocks(&ex.text)[4].lang.as_deref(), Some("rust"));
+    }
+
+    #[test]
+    fn test_body_fallback_and_sniffing() {
+        let ex = extract_html("<html><body><div>One<br>Two</div><p>Three</p></body></html>");
+        assert_eq!(ex.text, "One\nTwo\n\nThree");
+        assert!(looks_like_html("\u{FEFF}  <!doctype HTML><p>x"));
+        assert!(!looks_like_html("# Markdown <html>"));
+    }
+
+    #[test]
+    fn test_wrapped_paragraph_stays_prose() {
+        let ex = extract_html(
+            "<main><p>Prices rose by\n- 5% in May and\n1. in rank, see\n# 42 or\n| a | b |</p><p>x<br>- y</p></main>",
+        );
+        assert_eq!(ex.text, "Prices rose by - 5% in May and 1. in rank, see # 42 or | a | b |\n\nx\n\\- y");
+        let kinds: Vec<BlockKind> = parse_blocks(&ex.text).iter().map(|b| b.kind).collect();
+        assert_eq!(kinds, vec![BlockKind::Paragraph, BlockKind::Paragraph]);
+        assert_eq!(escape_marker("1) step"), "1\\) step");
+        assert_eq!(escape_marker("-5 degrees"), "-5 degrees");
+    }
+
+    #[test]
+    fn test_compound_class_names_are_content() {
+        let ex = extract_html(
+            r#"<body><div class="sidebar">Related</div><main>
+            <p class="share-price">Shares closed at 12.</p>
+            <section id="menu-pricing"><p>Lunch menu costs 9.</p></section>
+            <div class="ads-policy">We do not sell data.</div>
+            <p class="modal-verbs">Can, may, must.</p>
+            <div class="site-footer">Footer links</div>
+            </main></body>"#,
+        );
+        for kept in ["Shares closed", "Lunch menu", "sell data", "Can, may"] {
+            assert!(ex.text.contains(kept), "{kept} dropped: {}", ex.text);
+        }
+        assert!(!ex.text.contains("Related"));
+        assert!(!ex.text.contains("Footer links"));
+    }
+
+    #[test]
+    fn test_pre_with_backticks() {
+        let ex = extract_html("<pre><code class=\"language-md\">Run:\n```sh\nls\n```\n</code></pre>");
+        assert_eq!(ex.text, "````md\nRun:\n```sh\nls\n```\n````");
+        // The inner fence does not end the block
+        let blocks = crate::chunking::parse_blocks(&ex.text);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].kind, crate::chunking::BlockKind::CodeFence);
+        assert_eq!(blocks[0].lang.as_deref(), Some("md"));
+    }
+}
@@ -0,0 +1,251 @@
+// file: src/extract/mail.rs
+//
+// Почта: .eml — одно письмо, mbox — архив писем; каждое письмо — отдельная
+// `Record`. MIME и кодировки разбирает mail-parser, из тела выкидываются
+// цитаты предыдущих писем и подписи. Заголовки (from/to/subject/date,
+// message-id, in-reply-to, thread_id) — в метаданные для фильтров и тредов.
+
+use std::io::Cursor;
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use mail_parser::{Address, MessageParser};
+use regex::Regex;
+use serde_json::Value;
+
+use super::{extension, Record};
+use crate::chunking::DocumentMeta;
+
+lazy_static! {
+    /// "On Mon, 1 Jan 2024 Alice <a@x> wrote:", "1 янв. 2024 г., Иван написал(а):"
+    static ref RE_REPLY_HEADER: Regex =
+        Regex::new(r"(?i)^(on\b.*\bwrote|.*\b(написал|писал)(\(а\))?)\s*:\s*$").unwrap();
+    /// Разделители пересылки/цитаты Outlook и почтовых клиентов
+    static ref RE_ORIGINAL: Regex = Regex::new(
+        r"(?i)^-{2,}\s*(original message|forwarded message|исходное сообщение|пересылаемое сообщение)\s*-{2,}\s*$"
+    )
+    .unwrap();
+    /// Блок заголовков цитаты Outlook: "From: ..." и следом "Sent:"/"Date:"
+    static ref RE_OUTLOOK_FROM: Regex = Regex::new(r"(?i)^\s*(from|от|отправлено|кому)\s*:").unwrap();
+    static ref RE_OUTLOOK_NEXT: Regex = Regex::new(r"(?i)^\s*(sent|date|to|subject|дата|тема|кому)\s*:").unwrap();
+    static ref RE_MOBILE_SIG: Regex =
+        Regex::new(r"(?i)^(sent from my \w+|отправлено с (моего )?\w+|get outlook for \w+)").unwrap();
+}
+
+/// Почтовый ли файл (по расширению).
+pub fn is_mail_format(name: &str) -> bool {
+    matches!(extension(name).as_str(), "eml" | "mbox" | "mbx")
+}
+
+/// Письма файла: `.eml` — одно, `.mbox`/`.mbx` (или текст с "From " в начале
+/// без расширения) — все письма архива. `None` — не почта.
+pub fn parse_mail(name: &str, bytes: &[u8]) -> Result<Option<Vec<Record>>> {
+    match extension(name).as_str() {
+        "eml" => parse_eml(bytes).map(|r| Some(vec![r])),
+        "mbox" | "mbx" => parse_mbox(bytes).map(Some),
+        "" if bytes.starts_with(b"From ") => parse_mbox(bytes).map(Some),
+        _ => Ok(None),
+    }
+}
+
+pub fn parse_mbox(bytes: &[u8]) -> Result<Vec<Record>> {
+    let mut records = Vec::new();
+    for (i, message) in mail_parser::mailbox::mbox::MessageIterator::new(Cursor::new(bytes)).enumerate() {
+        let message = message?;
+        match parse_eml(message.contents()) {
+            Ok(record) => records.push(record),
+            Err(e) => eprintln!("⚠️  WARN: skipping mbox message {}: {}", i + 1, e),
+        }
+    }
+    if records.is_empty() {
+        return Err(anyhow!("mbox contains no readable messages"));
+    }
+    Ok(records)
+}
+
+/// Одно письмо: текст — `# Тема` и очищенное тело, `id` — Message-ID.
+pub fn parse_eml(bytes: &[u8]) -> Result<Record> {
+    let msg = MessageParser::default()
+        .parse(bytes)
+        .ok_or_else(|| anyhow!("not an RFC 5322 message"))?;
+
+    let subject = msg.subject().map(str::trim).filter(|s| !s.is_empty());
+    let body = msg.body_text(0).map(|b| clean_body(&b)).unwrap_or_default();
+    let mut text = subject.map(|s| format!("# {}", s)).unwrap_or_default();
+    if !body.is_empty() {
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+        text.push_str(&body);
+    }
+    if text.is_empty() {
+        return Err(anyhow!("message has neither subject nor text body"));
+    }
+
+    let id = msg.message_id().map(str::to_string);
+    let in_reply_to: Vec<String> = text_list(msg.in_reply_to());
+    let references: Vec<String> = text_list(msg.references());
+
+    let mut meta = DocumentMeta {
+        title: subject.map(str::to_string),
+        author: msg
+            .from()
+            .and_then(|a| a.first())
+            .and_then(|a| a.name().or(a.address()))
+            .map(str::to_string),
+        ..Default::default()
+    };
+    let mut put = |key: &str, value: Value| {
+        meta.extra.insert(key.to_string(), value);
+    };
+    if let Some(from) = msg.from().and_then(|a| a.first()).and_then(|a| a.address()) {
+        put("from", Value::from(from.to_ascii_lowercase()));
+    }
+    for (key, addrs) in [("to", msg.to()), ("cc", msg.cc())] {
+        let list = addresses(addrs);
+        if !list.is_empty() {
+            put(key, Value::from(list));
+        }
+    }
+    if let Some(subject) = subject {
+        put("subject", Value::from(subject));
+    }
+    if let Some(date) = msg.date() {
+        put("date", Value::from(date.to_rfc3339()));
+        put("timestamp", Value::from(date.to_timestamp()));
+    }
+    if let Some(id) = &id {
+        put("message_id", Value::from(id.clone()));
+    }
+    if let Some(parent) = in_reply_to.first() {
+        put("in_reply_to", Value::from(parent.clone()));
+    }
+    if !references.is_empty() {
+        put("references", Value::from(references.clone()));
+    }
+    // Thread root: the first reference, else the parent, else the message itself
+    if let Some(thread) = references.first().or(in_reply_to.first()).or(id.as_ref()) {
+        put("thread_id", Value::from(thread.clone()));
+    }
+
+    Ok(Record { id, text, meta })
+}
+
+fn text_list(value: &mail_parser::HeaderValue) -> Vec<String> {
+    value
+        .as_text_list()
+        .map(|l| l.iter().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+fn addresses(addr: Option<&Address>) -> Vec<String> {
+    addr.map(|a| {
+        a.iter()
+            .filter_map(|a| a.address())
+            .map(str::to_ascii_lowercase)
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Тело без цитат (`>`-строки, "On … wrote:" и всё после разделителя
+/// пересланного/исходного письма) и без подписи (`-- ` и "Sent from my …").
+pub fn clean_body(body: &str) -> String {
+    let lines: Vec<&str> = body.lines().map(|l| l.trim_end_matches('\r')).collect();
+    let mut kept: Vec<&str> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        // Everything below these markers is someone else's text or a signature
+        if trimmed == "--"
+            || *line == "-- "
+            || RE_ORIGINAL.is_match(trimmed)
+            || RE_MOBILE_SIG.is_match(trimmed)
+            || RE_REPLY_HEADER.is_match(trimmed)
+            || (RE_OUTLOOK_FROM.is_match(line)
+                && lines[i + 1..].iter().take(3).any(|l| RE_OUTLOOK_NEXT.is_match(l)))
+        {
+            break;
+        }
+        if trimmed.starts_with('>') {
+            continue;
+        }
+        kept.push(line);
+    }
+
+    // Collapse runs of blank lines left by removed quotes
+    let mut out = String::new();
+    let mut blank = false;
+    for line in kept {
+        if line.trim().is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+        if blank {
+            out.push('\n');
+            blank = false;
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(line.trim_end());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPLY: &str = "From: =?UTF-8?B?0JjQstCw0L0g0J/QtdGC0YDQvtCy?= <Ivan@Example.com>\r\n\
+To: Team <team@example.com>, bob@example.com\r\n\
+Subject: =?KOI8-R?B?8MzBziDSxczJ2sE=?=\r\n\
+Date: Tue, 4 Jun 2024 10:15:00 +0300\r\n\
+Message-ID: <reply-2@example.com>\r\n\
+In-Reply-To: <root-1@example.com>\r\n\
+References: <root-1@example.com>\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: text/plain; charset=windows-1251\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+=D1=EE=E3=EB=E0=F1=E5=ED, =E2=FB=EA=E0=F2=FB=E2=E0=E5=EC =E2 =EF=FF=F2=ED=E8=F6=F3.\r\n\
+\r\n\
+On Mon, 3 Jun 2024 Alice <alice@example.com> wrote:\r\n\
+> When do we ship?\r\n";
+
+    #[test]
+    fn test_eml_headers_and_body() {
+        let record = parse_eml(REPLY.as_bytes()).unwrap();
+        assert_eq!(record.id.as_deref(), Some("reply-2@example.com"));
+        assert_eq!(record.text, "# План релиза\n\nСогласен, выкатываем в пятницу.");
+
+        let meta = &record.meta;
+        assert_eq!(meta.title.as_deref(), Some("План релиза"));
+        assert_eq!(meta.author.as_deref(), Some("Иван Петров"));
+        assert_eq!(meta.extra["from"], "ivan@example.com");
+        assert_eq!(meta.extra["to"], serde_json::json!(["team@example.com", "bob@example.com"]));
+        assert_eq!(meta.extra["date"], "2024-06-04T10:15:00+03:00");
+        assert_eq!(meta.extra["in_reply_to"], "root-1@example.com");
+        assert_eq!(meta.extra["thread_id"], "root-1@example.com");
+    }
+
+    #[test]
+    fn test_mbox_and_cleaning() {
+        let mbox = "From alice@example.com Mon Jun  3 09:00:00 2024\n\
+Subject: Release\nMessage-ID: <root-1@example.com>\n\n\
+When do we ship?\n\n-- \nAlice, PM\n\
+From ivan@example.com Tue Jun  4 10:15:00 2024\n\
+Subject: Re: Release\nMessage-ID: <reply-2@example.com>\n\n\
+Friday.\n\n-----Original Message-----\nFrom: Alice\nSent: Monday\n\nWhen do we ship?\n";
+        let records = parse_mail("archive.mbox", mbox.as_bytes()).unwrap().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].text, "# Release\n\nWhen do we ship?");
+        assert_eq!(records[0].meta.extra["thread_id"], "root-1@example.com");
+        assert_eq!(records[1].text, "# Re: Release\n\nFriday.");
+
+        assert_eq!(
+            clean_body("Да.\n\nОт: Иван\nОтправлено: вт\nТема: x\n\nстарое"),
+            "Да."
+        );
+        assert!(parse_mail("notes.txt", b"From here on").unwrap().is_none());
+    }
+}
@@ -0,0 +1,157 @@
+// file: src/extract/mod.rs
+//
+// Извлечение текста из форматов, которые нельзя индексировать как есть:
+// на выходе markdown-подобный текст (его понимает `parse_blocks`) и метаданные.
+
+mod archive;
+mod epub;
+mod html;
+mod mail;
+mod notebook;
+mod office;
+mod pdf;
+mod records;
+mod sniff;
+mod subtitles;
+
+pub use archive::{unpack_archive, ArchiveEntry, ArchiveKind, ArchiveLimits};
+pub use epub::extract_epub;
+pub use html::{extract_html, looks_like_html};
+pub use mail::{clean_body, is_mail_format, parse_eml, parse_mail, parse_mbox};
+pub use notebook::{extract_notebook, is_notebook};
+pub use office::{extract_docx, extract_odt};
+pub use pdf::extract_pdf;
+pub use records::{is_record_format, parse_records, Record};
+pub use sniff::{
+    decode_to_utf8_hard, gunzip, sniff_content, text_encoding, ContentType, UnsupportedContent,
+};
+pub use subtitles::{extract_subtitles, is_subtitle_format, parse_cues, Cue};
+
+use std::collections::BTreeMap;
+
+use crate::chunking::DocumentMeta;
+
+/// Zip из пар `(путь, содержимое)` для тестов экстракторов.
+#[cfg(test)]
+pub(crate) fn test_zip(parts: &[(&str, &[u8])]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, content) in parts {
+        zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+        zip.write_all(content).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+/// Текст документа, готовый к чанкингу, и его метаданные.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Extracted {
+    pub text: String,
+    pub meta: DocumentMeta,
+    /// Участки текста со своими полями payload (страницы PDF и т.п.)
+    pub regions: Vec<Region>,
+}
+
+/// Участок `text[start..end]` с полями, которые получают пересекающие его чанки.
+/// Чанк на несколько участков берёт значения первого, а поля с суффиксом
+/// `_end` — последнего (`page`/`page_end` → первая и последняя страница).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Region {
+    pub start: usize,
+    pub end: usize,
+    pub fields: BTreeMap<String, serde_json::Value>,
+}
+
+/// Поля payload для спана `start..end` по участкам `regions` (отсортированы по `start`).
+pub fn region_fields(regions: &[Region], start: usize, end: usize) -> BTreeMap<String, serde_json::Value> {
+    let mut hits = regions
+        .iter()
+        .filter(|r| r.start < end.max(start + 1) && r.end > start);
+    let Some(first) = hits.next() else {
+        return BTreeMap::new();
+    };
+    let mut fields = first.fields.clone();
+    if let Some(last) = hits.next_back() {
+        for (k, v) in &last.fields {
+            if k.ends_with("_end") {
+                fields.insert(k.clone(), v.clone());
+            }
+        }
+    }
+    fields
+}
+
+/// Таблица в markdown: первая строка — заголовок, `|` в ячейках экранируется.
+fn markdown_table(rows: &[Vec<String>]) -> String {
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if width == 0 {
+        return String::new();
+    }
+
+    let line = |cells: &[String]| {
+        let mut padded: Vec<String> = cells.iter().map(|c| c.replace('|', "\\|")).collect();
+        padded.resize(width, String::new());
+        format!("| {} |", padded.join(" | "))
+    };
+    let mut lines = vec![line(&rows[0]), format!("|{}", " --- |".repeat(width))];
+    lines.extend(rows[1..].iter().map(|r| line(r)));
+    lines.join("\n")
+}
+
+/// Fenced code block; забор на один backtick длиннее самой длинной серии
+/// backtick-ов в коде (минимум три), иначе код закроет блок раньше времени.
+fn code_fence(lang: &str, code: &str) -> String {
+    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat((longest + 1).max(3));
+    format!("{}{}\n{}\n{}", fence, lang, code, fence)
+}
+
+/// Экранирует маркер markdown в начале строки абзаца (`\#`, `\- `, `1\. `),
+/// чтобы `parse_blocks` не принял текст за заголовок, список или таблицу.
+fn escape_marker(line: &str) -> String {
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let rest = &line[digits..];
+    if (1..=3).contains(&digits) && (rest.starts_with(". ") || rest.starts_with(") ")) {
+        return format!("{}\\{}", &line[..digits], rest);
+    }
+    let first = line.chars().next().unwrap_or(' ');
+    let marker = match first {
+        '#' | '>' | '|' => true,
+        '`' => line.starts_with("```"),
+        '-' | '+' | '*' => line[1..].starts_with(' ') || line.chars().all(|c| c == first || c == ' '),
+        '_' => line.chars().all(|c| c == '_' || c == ' '),
+        _ => false,
+    };
+    if marker {
+        format!("\\{}", line)
+    } else {
+        line.to_string()
+    }
+}
+
+/// Бинарные данные без своего экстрактора (картинки, исполняемые файлы):
+/// NUL-байты в начале файла в тексте не встречаются.
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8192).any(|&b| b == 0)
+}
+
+/// Расширение имени файла в нижнем регистре.
+pub fn extension(name: &str) -> String {
+    std::path::Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase()
+}
+
+/// Экстрактор для уже декодированного текстового файла. `None` —
+/// текст индексируется как есть (markdown, plain text).
+pub fn extract_text(name: &str, text: &str) -> Option<Extracted> {
+    match extension(name).as_str() {
+        "html" | "htm" | "xhtml" => Some(extract_html(text)),
+        "srt" | "vtt" => extract_subtitles(text),
+        "" | "txt" if looks_like_html(text) => Some(extract_html(text)),
+        _ => None,
+    }
+}
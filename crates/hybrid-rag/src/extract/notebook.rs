@@ -0,0 +1,220 @@
+// file: src/extract/notebook.rs
+//
+// Jupyter (.ipynb, nbformat 4): markdown-ячейки идут как есть (их разбирает
+// `parse_blocks`), код — блоком ```lang с языком ядра, выводы ячеек — по
+// настройке `NotebookConfig`. Номер ячейки — `Region` с полями `cell`/`cell_end`.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+
+use super::{code_fence, extension, Extracted, Region};
+use crate::config::NotebookConfig;
+
+/// Ноутбук ли это (по расширению).
+pub fn is_notebook(name: &str) -> bool {
+    extension(name) == "ipynb"
+}
+
+pub fn extract_notebook(text: &str, cfg: &NotebookConfig) -> Result<Extracted> {
+    let nb: Value = serde_json::from_str(text.trim_start_matches('\u{FEFF}')).context("invalid notebook JSON")?;
+    let format = nb.get("nbformat").and_then(Value::as_u64).unwrap_or(0);
+    if format < 4 {
+        return Err(anyhow!("unsupported nbformat {} (expected 4)", format));
+    }
+    let cells = nb
+        .get("cells")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("notebook has no cells"))?;
+    let metadata = nb.get("metadata");
+    let lang = kernel_language(metadata);
+
+    let mut out = Extracted::default();
+    for (index, cell) in cells.iter().enumerate() {
+        let source = joined(cell.get("source"));
+        let source = source.trim_matches('\n');
+        let mut parts: Vec<String> = Vec::new();
+        match cell.get("cell_type").and_then(Value::as_str) {
+            Some("markdown") if !source.trim().is_empty() => parts.push(source.to_string()),
+            Some("code") => {
+                if !source.trim().is_empty() {
+                    parts.push(code_fence(&lang, source));
+                }
+                if cfg.include_outputs {
+                    let outputs = cell.get("outputs").and_then(Value::as_array).map(Vec::as_slice).unwrap_or(&[]);
+                    parts.extend(outputs.iter().filter_map(output_text).map(|o| code_fence("text", &o)));
+                }
+            }
+            // Raw cells and empty cells carry nothing to search
+            _ => {}
+        }
+        if parts.is_empty() {
+            continue;
+        }
+
+        if !out.text.is_empty() {
+            out.text.push_str("\n\n");
+        }
+        let start = out.text.len();
+        out.text.push_str(&parts.join("\n\n"));
+        let mut region = Region {
+            start,
+            end: out.text.len(),
+            ..Default::default()
+        };
+        region.fields.insert("cell".into(), Value::from(index));
+        region.fields.insert("cell_end".into(), Value::from(index));
+        if let Some(id) = cell.get("id").and_then(Value::as_str) {
+            region.fields.insert("cell_id".into(), Value::from(id));
+        }
+        out.regions.push(region);
+    }
+    if out.text.is_empty() {
+        return Err(anyhow!("notebook has no non-empty cells"));
+    }
+
+    out.meta.title = metadata
+        .and_then(|m| m.get("title"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| {
+            out.text
+                .lines()
+                .find_map(|l| l.strip_prefix("# "))
+                .map(|t| t.trim().to_string())
+        });
+    if let Some(authors) = metadata.and_then(|m| m.get("authors")).and_then(Value::as_array) {
+        let names: Vec<&str> = authors.iter().filter_map(|a| a.get("name")?.as_str()).collect();
+        if !names.is_empty() {
+            out.meta.author = Some(names.join(", "));
+        }
+    }
+    out.meta.extra.insert("kernel_language".into(), Value::from(lang));
+    Ok(out)
+}
+
+/// Язык ядра для блока кода: `kernelspec.language`, иначе `language_info.name`.
+/// Приводится к виду, который понимает разбор ```-блоков (`c#` → `csharp`).
+fn kernel_language(metadata: Option<&Value>) -> String {
+    let lang = metadata
+        .and_then(|m| {
+            m.pointer("/kernelspec/language")
+                .or_else(|| m.pointer("/language_info/name"))
+        })
+        .and_then(Value::as_str)
+        .unwrap_or("python")
+        .to_ascii_lowercase()
+        .replace('#', "sharp");
+    let lang: String = lang
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '+' | '-'))
+        .collect();
+    if lang.is_empty() {
+        "python".into()
+    } else {
+        lang
+    }
+}
+
+/// `source`/`text` в nbformat — строка или массив строк (с `\n` внутри).
+fn joined(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Текстовый вывод ячейки: stdout/stderr, `text/plain` результата, ошибка
+/// как `Name: value` (traceback с ANSI-цветами в индекс не идёт). Картинки и HTML — нет.
+fn output_text(output: &Value) -> Option<String> {
+    let text = match output.get("output_type")?.as_str()? {
+        "stream" => joined(output.get("text")),
+        "execute_result" | "display_data" => joined(output.pointer("/data/text~1plain")),
+        "error" => format!(
+            "{}: {}",
+            output.get("ename")?.as_str()?,
+            output.get("evalue").and_then(Value::as_str).unwrap_or("")
+        ),
+        _ => return None,
+    };
+    let text = text.trim_matches('\n');
+    (!text.trim().is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunking::{parse_blocks, BlockKind};
+    use crate::extract::region_fields;
+
+    const NOTEBOOK: &str = r##"{
+      "nbformat": 4, "nbformat_minor": 5,
+      "metadata": {"kernelspec": {"name": "ir", "language": "R", "display_name": "R"}},
+      "cells": [
+        {"cell_type": "markdown", "id": "intro", "metadata": {}, "source": ["# Sales analysis\n", "\n", "Quarterly *totals*."]},
+        {"cell_type": "code", "id": "load", "execution_count": 1, "metadata": {}, "source": "df <- read.csv('sales.csv')\nsummary(df)",
+         "outputs": [
+           {"output_type": "stream", "name": "stdout", "text": ["  region  total\n"]},
+           {"output_type": "display_data", "data": {"image/png": "iVBOR..."}, "metadata": {}}
+         ]},
+        {"cell_type": "code", "id": "empty", "metadata": {}, "source": [], "outputs": []},
+        {"cell_type": "code", "id": "fail", "metadata": {}, "source": "stop('boom')",
+         "outputs": [{"output_type": "error", "ename": "simpleError", "evalue": "boom", "traceback": ["\u001b[31m..."]}]}
+      ]
+    }"##;
+
+    #[test]
+    fn test_cells_and_blocks() {
+        let ex = extract_notebook(NOTEBOOK, &NotebookConfig::default()).unwrap();
+        assert_eq!(
+            ex.text,
+            "# Sales analysis\n\nQuarterly *totals*.\n\n```r\ndf <- read.csv('sales.csv')\nsummary(df)\n```\n\n```r\nstop('boom')\n```"
+        );
+        assert_eq!(ex.meta.title.as_deref(), Some("Sales analysis"));
+        assert_eq!(ex.meta.extra["kernel_language"], "r");
+
+        let fences: Vec<_> = parse_blocks(&ex.text)
+            .into_iter()
+            .filter(|b| b.kind == BlockKind::CodeFence)
+            .collect();
+        assert_eq!(fences.len(), 2);
+        assert_eq!(fences[0].lang.as_deref(), Some("r"));
+
+        // The empty cell is skipped but keeps the numbering of the cells array
+        let at = ex.text.find("stop(").unwrap();
+        let fields = region_fields(&ex.regions, at, ex.text.len());
+        assert_eq!(fields["cell"], 3);
+        assert_eq!(fields["cell_id"], "fail");
+        let fields = region_fields(&ex.regions, 0, at);
+        assert_eq!((fields["cell"].clone(), fields["cell_end"].clone()), (0.into(), 3.into()));
+    }
+
+    #[test]
+    fn test_outputs_optional() {
+        let cfg = NotebookConfig { include_outputs: true };
+        let ex = extract_notebook(NOTEBOOK, &cfg).unwrap();
+        assert!(ex.text.contains("summary(df)\n```\n\n```text\n  region  total\n```"));
+        assert!(ex.text.ends_with("```text\nsimpleError: boom\n```"));
+        assert!(!ex.text.contains("iVBOR"));
+
+        assert!(extract_notebook(r#"{"nbformat": 3, "worksheets": []}"#, &cfg).is_err());
+        assert!(is_notebook("Analysis.IPYNB"));
+    }
+
+    #[test]
+    fn test_backticks_in_cell() {
+        let nb = r#"{"nbformat": 4, "metadata": {"language_info": {"name": "python"}}, "cells": [
+            {"cell_type": "code", "metadata": {}, "source": "doc = '''\n```sh\nls\n```\n'''",
+             "outputs": [{"output_type": "stream", "name": "stdout", "text": "````\n"}]}
+        ]}"#;
+        let cfg = NotebookConfig { include_outputs: true };
+        let ex = extract_notebook(nb, &cfg).unwrap();
+        assert!(ex.text.starts_with("````python\ndoc = '''\n```sh\nls\n```\n'''\n````"));
+        assert!(ex.text.ends_with("`````text\n````\n`````"));
+        let fences: Vec<_> = parse_blocks(&ex.text)
+            .into_iter()
+            .filter(|b| b.kind == BlockKind::CodeFence)
+            .collect();
+        assert_eq!(fences.len(), 2);
+    }
+}
@@ -0,0 +1,722 @@
+// file: src/extract/office.rs
+//
+// DOCX и ODT → markdown-подобный текст: стили заголовков становятся `#`,
+// списки — `- `/`1. ` с отступами, таблицы — markdown-таблицами.
+// Свойства документа (title, author, даты, ключевые слова) — в метаданные.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+use anyhow::{anyhow, Context, Result};
+use roxmltree::{Document, Node};
+use zip::ZipArchive;
+
+use super::{escape_marker, markdown_table, Extracted};
+use crate::chunking::DocumentMeta;
+
+const W_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+const TEXT_NS: &str = "urn:oasis:names:tc:opendocument:xmlns:text:1.0";
+const TABLE_NS: &str = "urn:oasis:names:tc:opendocument:xmlns:table:1.0";
+const STYLE_NS: &str = "urn:oasis:names:tc:opendocument:xmlns:style:1.0";
+
+/// Защита от zip-бомб: распакованная XML-часть не больше этого.
+const MAX_PART_BYTES: u64 = 64 * 1024 * 1024;
+
+pub(super) type Archive<'a> = ZipArchive<Cursor<&'a [u8]>>;
+
+pub(super) fn open_zip(bytes: &[u8]) -> Result<Archive<'_>> {
+    ZipArchive::new(Cursor::new(bytes)).context("not a valid zip container")
+}
+
+/// Часть архива как UTF-8 строка; `None`, если её нет.
+pub(super) fn read_part(zip: &mut Archive, name: &str) -> Result<Option<String>> {
+    let file = match zip.by_name(name) {
+        Ok(f) => f,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("failed to open {}", name)),
+    };
+    let mut buf = Vec::new();
+    file.take(MAX_PART_BYTES + 1).read_to_end(&mut buf)?;
+    if buf.len() as u64 > MAX_PART_BYTES {
+        return Err(anyhow!("{} is larger than {} bytes", name, MAX_PART_BYTES));
+    }
+    Ok(Some(String::from_utf8_lossy(&buf).into_owned()))
+}
+
+fn parse_xml(xml: &str) -> Result<Document<'_>> {
+    Document::parse(xml).context("malformed XML")
+}
+
+fn is(node: Node, ns: &str, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name && node.tag_name().namespace() == Some(ns)
+}
+
+fn child<'a, 'i>(node: Node<'a, 'i>, ns: &str, name: &str) -> Option<Node<'a, 'i>> {
+    node.children().find(|c| is(*c, ns, name))
+}
+
+/// `w:val` и прочие атрибуты в namespace WordprocessingML.
+fn w_attr<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attribute((W_NS, name))
+}
+
+/// Схлопывает пробельные последовательности (переводы строк сохраняются).
+fn collapse_lines(s: &str) -> String {
+    s.split('\n')
+        .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Собирает блоки: абзацы через пустую строку, подряд идущие пункты списка — построчно.
+#[derive(Default)]
+struct Writer {
+    out: String,
+    in_list: bool,
+}
+
+impl Writer {
+    fn block(&mut self, text: &str) {
+        if text.trim().is_empty() {
+            return;
+        }
+        if !self.out.is_empty() {
+            self.out.push_str("\n\n");
+        }
+        self.out.push_str(text);
+        self.in_list = false;
+    }
+
+    /// Обычный абзац: маркеры markdown в начале строк экранируются.
+    fn paragraph(&mut self, text: &str) {
+        let lines: Vec<String> = text.lines().map(escape_marker).collect();
+        self.block(&lines.join("\n"));
+    }
+
+    fn list_item(&mut self, line: &str) {
+        if self.in_list {
+            self.out.push('\n');
+            self.out.push_str(line);
+        } else {
+            self.block(line);
+        }
+        self.in_list = true;
+    }
+}
+
+fn list_line(depth: usize, ordered: bool, n: usize, text: &str) -> String {
+    let marker = if ordered { format!("{}.", n) } else { "-".to_string() };
+    format!("{}{} {}", "  ".repeat(depth), marker, text)
+}
+
+// === DOCX ===
+
+/// Стиль абзаца: уровень заголовка и нумерация (для стилей вроде "List Bullet").
+#[derive(Debug, Default, Clone)]
+struct WordStyle {
+    heading: Option<usize>,
+    numbering: Option<(String, usize)>,
+}
+
+pub fn extract_docx(bytes: &[u8]) -> Result<Extracted> {
+    let mut zip = open_zip(bytes)?;
+    let document = read_part(&mut zip, "word/document.xml")?
+        .ok_or_else(|| anyhow!("word/document.xml is missing: not a DOCX file"))?;
+    let styles = match read_part(&mut zip, "word/styles.xml")? {
+        Some(xml) => word_styles(&parse_xml(&xml)?),
+        None => HashMap::new(),
+    };
+    let numbering = match read_part(&mut zip, "word/numbering.xml")? {
+        Some(xml) => word_numbering(&parse_xml(&xml)?),
+        None => HashMap::new(),
+    };
+
+    let doc = parse_xml(&document)?;
+    let body = doc
+        .descendants()
+        .find(|n| is(*n, W_NS, "body"))
+        .ok_or_else(|| anyhow!("DOCX has no w:body"))?;
+    let mut w = DocxWriter {
+        styles: &styles,
+        numbering: &numbering,
+        counters: HashMap::new(),
+        out: Writer::default(),
+    };
+    w.walk(body);
+
+    let mut out = Extracted {
+        text: w.out.out,
+        ..Default::default()
+    };
+    if let Some(xml) = read_part(&mut zip, "docProps/core.xml")? {
+        out.meta = core_properties(&parse_xml(&xml)?);
+    }
+    Ok(out)
+}
+
+/// Глубина цепочки `w:basedOn`, дальше которой стили не наследуются (и циклы).
+const MAX_STYLE_DEPTH: usize = 16;
+
+/// Стиль как он записан в styles.xml, до наследования.
+struct RawWordStyle<'a> {
+    based_on: Option<&'a str>,
+    heading: Option<usize>,
+    /// `Some(None)` — нумерация явно снята (numId "0")
+    numbering: Option<Option<(String, usize)>>,
+}
+
+/// styleId → заголовок/нумерация. Уровень берётся из `w:outlineLvl` или имени
+/// встроенного стиля ("heading 2", "Title"), имена в styles.xml не локализуются.
+/// Чего нет в самом стиле, наследуется по цепочке `w:basedOn`.
+fn word_styles(doc: &Document) -> HashMap<String, WordStyle> {
+    let mut raw: HashMap<&str, RawWordStyle> = HashMap::new();
+    for s in doc.descendants().filter(|n| is(*n, W_NS, "style")) {
+        let Some(id) = w_attr(s, "styleId") else { continue };
+        let name = child(s, W_NS, "name")
+            .and_then(|n| w_attr(n, "val"))
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let ppr = child(s, W_NS, "pPr");
+        let outline = ppr
+            .and_then(|p| child(p, W_NS, "outlineLvl"))
+            .and_then(|o| w_attr(o, "val"))
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|&l| l < 9)
+            .map(|l| l + 1);
+        let heading = outline.or_else(|| match name.as_str() {
+            "title" => Some(1),
+            _ => name.strip_prefix("heading ").and_then(|n| n.parse().ok()),
+        });
+        let numbering = ppr.and_then(|p| child(p, W_NS, "numPr")).map(num_pr);
+        let based_on = child(s, W_NS, "basedOn").and_then(|b| w_attr(b, "val"));
+        raw.insert(id, RawWordStyle { based_on, heading: heading.map(|h: usize| h.min(6)), numbering });
+    }
+
+    let mut styles = HashMap::new();
+    for &id in raw.keys() {
+        let (mut heading, mut numbering) = (None, None);
+        let mut current = raw.get(id);
+        for _ in 0..MAX_STYLE_DEPTH {
+            let Some(s) = current else { break };
+            heading = heading.or(s.heading);
+            numbering = numbering.or_else(|| s.numbering.clone());
+            current = s.based_on.and_then(|b| raw.get(b));
+        }
+        styles.insert(id.to_string(), WordStyle { heading, numbering: numbering.flatten() });
+    }
+    styles
+}
+
+/// numId → для каждого уровня: нумерованный (`true`) или маркированный список.
+fn word_numbering(doc: &Document) -> HashMap<String, Vec<bool>> {
+    let mut abstracts: HashMap<&str, Vec<bool>> = HashMap::new();
+    for a in doc.descendants().filter(|n| is(*n, W_NS, "abstractNum")) {
+        let Some(id) = w_attr(a, "abstractNumId") else { continue };
+        let mut levels = vec![false; 9];
+        for lvl in a.children().filter(|n| is(*n, W_NS, "lvl")) {
+            let ilvl: usize = w_attr(lvl, "ilvl").and_then(|v| v.parse().ok()).unwrap_or(0);
+            let fmt = child(lvl, W_NS, "numFmt").and_then(|f| w_attr(f, "val")).unwrap_or("bullet");
+            if let Some(slot) = levels.get_mut(ilvl) {
+                *slot = !matches!(fmt, "bullet" | "none");
+            }
+        }
+        abstracts.insert(id, levels);
+    }
+    doc.descendants()
+        .filter(|n| is(*n, W_NS, "num"))
+        .filter_map(|num| {
+            let id = w_attr(num, "numId")?;
+            let abs = child(num, W_NS, "abstractNumId").and_then(|a| w_attr(a, "val"))?;
+            Some((id.to_string(), abstracts.get(abs)?.clone()))
+        })
+        .collect()
+}
+
+/// `w:numPr` → (numId, ilvl); numId "0" означает «нумерация снята».
+fn num_pr(node: Node) -> Option<(String, usize)> {
+    let id = child(node, W_NS, "numId").and_then(|n| w_attr(n, "val"))?;
+    if id == "0" {
+        return None;
+    }
+    let ilvl = child(node, W_NS, "ilvl")
+        .and_then(|n| w_attr(n, "val"))
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    Some((id.to_string(), ilvl))
+}
+
+struct DocxWriter<'s> {
+    styles: &'s HashMap<String, WordStyle>,
+    numbering: &'s HashMap<String, Vec<bool>>,
+    /// Счётчики нумерованных списков по (numId, ilvl)
+    counters: HashMap<(String, usize), usize>,
+    out: Writer,
+}
+
+impl DocxWriter<'_> {
+    fn walk(&mut self, node: Node) {
+        for c in node.children().filter(|c| c.is_element()) {
+            if is(c, W_NS, "p") {
+                self.paragraph(c);
+            } else if is(c, W_NS, "tbl") {
+                self.out.block(&docx_table(c));
+            } else if c.tag_name().namespace() == Some(W_NS)
+                && !matches!(c.tag_name().name(), "del" | "moveFrom" | "sectPr")
+            {
+                // Content controls, custom XML, smart tags and tracked insertions
+                // wrap ordinary paragraphs and tables; deletions are dropped
+                self.walk(c);
+            }
+        }
+    }
+
+    fn paragraph(&mut self, p: Node) {
+        let text = collapse_lines(&docx_runs(p));
+        if text.is_empty() {
+            return;
+        }
+        let ppr = child(p, W_NS, "pPr");
+        let style = ppr
+            .and_then(|pr| child(pr, W_NS, "pStyle"))
+            .and_then(|s| w_attr(s, "val"))
+            .and_then(|id| self.styles.get(id))
+            .cloned()
+            .unwrap_or_default();
+        let outline = ppr
+            .and_then(|pr| child(pr, W_NS, "outlineLvl"))
+            .and_then(|o| w_attr(o, "val"))
+            .and_then(|v| v.parse::<usize>().ok())
+            .filter(|&l| l < 9)
+            .map(|l| (l + 1).min(6));
+
+        if let Some(level) = outline.or(style.heading) {
+            self.out.block(&format!("{} {}", "#".repeat(level), text.replace('\n', " ")));
+            return;
+        }
+        let numbering = ppr
+            .and_then(|pr| child(pr, W_NS, "numPr"))
+            .and_then(num_pr)
+            .or(style.numbering);
+        match numbering {
+            Some((id, ilvl)) => {
+                let ordered = self
+                    .numbering
+                    .get(&id)
+                    .and_then(|l| l.get(ilvl).copied())
+                    .unwrap_or(false);
+                // A shallower item restarts the deeper levels of the same list
+                self.counters.retain(|(n, l), _| n != &id || *l <= ilvl);
+                let n = self.counters.entry((id, ilvl)).or_insert(0);
+                *n += 1;
+                self.out.list_item(&list_line(ilvl, ordered, *n, &text.replace('\n', " ")));
+            }
+            None => self.out.paragraph(&text),
+        }
+    }
+}
+
+/// Текст абзаца: `w:t`, табуляции и переносы; удалённые правки (`w:delText`) не попадают.
+fn docx_runs(p: Node) -> String {
+    let mut s = String::new();
+    for n in p.descendants().filter(|n| n.is_element()) {
+        if n.tag_name().namespace() != Some(W_NS) {
+            continue;
+        }
+        match n.tag_name().name() {
+            "t" => s.push_str(n.text().unwrap_or("")),
+            "tab" if n.parent().is_some_and(|p| is(p, W_NS, "r")) => s.push(' '),
+            "br" | "cr" => s.push('\n'),
+            "noBreakHyphen" => s.push('-'),
+            _ => {}
+        }
+    }
+    s
+}
+
+fn docx_table(tbl: Node) -> String {
+    let rows: Vec<Vec<String>> = tbl
+        .children()
+        .filter(|r| is(*r, W_NS, "tr"))
+        .map(|tr| {
+            tr.children()
+                .filter(|c| is(*c, W_NS, "tc"))
+                .map(|tc| {
+                    let parts: Vec<String> = tc
+                        .descendants()
+                        .filter(|d| is(*d, W_NS, "p"))
+                        .map(|p| collapse_lines(&docx_runs(p)).replace('\n', " "))
+                        .filter(|t| !t.is_empty())
+                        .collect();
+                    parts.join(" ")
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|r: &Vec<String>| !r.is_empty())
+        .collect();
+    markdown_table(&rows)
+}
+
+/// docProps/core.xml: title/creator/keywords → поля `DocumentMeta`, даты и прочее — в `extra`.
+fn core_properties(doc: &Document) -> DocumentMeta {
+    let mut meta = DocumentMeta::default();
+    for n in doc.root_element().children().filter(|n| n.is_element()) {
+        let value = n.text().unwrap_or("").trim();
+        if value.is_empty() {
+            continue;
+        }
+        match n.tag_name().name() {
+            "title" => meta.title = Some(value.to_string()),
+            "creator" => meta.author = Some(value.to_string()),
+            "keywords" => meta.tags = split_keywords(value),
+            "lastModifiedBy" => insert_extra(&mut meta, "last_modified_by", value),
+            "created" => insert_extra(&mut meta, "created", value),
+            "modified" => insert_extra(&mut meta, "modified", value),
+            "subject" => insert_extra(&mut meta, "subject", value),
+            "description" => insert_extra(&mut meta, "description", value),
+            _ => {}
+        }
+    }
+    meta
+}
+
+fn split_keywords(s: &str) -> Vec<String> {
+    s.split([',', ';'])
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn insert_extra(meta: &mut DocumentMeta, key: &str, value: &str) {
+    meta.extra.insert(key.to_string(), serde_json::Value::from(value.to_string()));
+}
+
+// === ODT ===
+
+pub fn extract_odt(bytes: &[u8]) -> Result<Extracted> {
+    let mut zip = open_zip(bytes)?;
+    let content = read_part(&mut zip, "content.xml")?
+        .ok_or_else(|| anyhow!("content.xml is missing: not an ODT file"))?;
+    let doc = parse_xml(&content)?;
+
+    // List styles live in content.xml (automatic) and styles.xml (named)
+    let mut list_styles = odt_list_styles(&doc);
+    if let Some(xml) = read_part(&mut zip, "styles.xml")? {
+        list_styles.extend(odt_list_styles(&parse_xml(&xml)?));
+    }
+
+    let body = doc
+        .descendants()
+        .find(|n| n.tag_name().name() == "text" && n.parent().is_some_and(|p| p.tag_name().name() == "body"))
+        .ok_or_else(|| anyhow!("ODT has no office:text body"))?;
+    let mut w = OdtWriter {
+        list_styles: &list_styles,
+        out: Writer::default(),
+    };
+    w.walk(body);
+
+    let mut out = Extracted {
+        text: w.out.out,
+        ..Default::default()
+    };
+    if let Some(xml) = read_part(&mut zip, "meta.xml")? {
+        out.meta = odt_properties(&parse_xml(&xml)?);
+    }
+    Ok(out)
+}
+
+/// Имя стиля списка → для каждого уровня: нумерованный или нет.
+fn odt_list_styles(doc: &Document) -> HashMap<String, Vec<bool>> {
+    doc.descendants()
+        .filter(|n| is(*n, TEXT_NS, "list-style"))
+        .filter_map(|s| {
+            let name = s.attribute((STYLE_NS, "name"))?;
+            let mut levels = vec![false; 10];
+            for lvl in s.children().filter(|c| c.is_element()) {
+                let level: usize = lvl.attribute((TEXT_NS, "level")).and_then(|v| v.parse().ok()).unwrap_or(1);
+                if let Some(slot) = levels.get_mut(level.saturating_sub(1)) {
+                    *slot = is(lvl, TEXT_NS, "list-level-style-number");
+                }
+            }
+            Some((name.to_string(), levels))
+        })
+        .collect()
+}
+
+struct OdtWriter<'s> {
+    list_styles: &'s HashMap<String, Vec<bool>>,
+    out: Writer,
+}
+
+impl OdtWriter<'_> {
+    fn walk(&mut self, node: Node) {
+        for c in node.children().filter(|c| c.is_element()) {
+            if is(c, TEXT_NS, "h") {
+                let level: usize = c
+                    .attribute((TEXT_NS, "outline-level"))
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(1);
+                let text = collapse_lines(&odt_inline(c)).replace('\n', " ");
+                if !text.is_empty() {
+                    self.out.block(&format!("{} {}", "#".repeat(level.clamp(1, 6)), text));
+                }
+            } else if is(c, TEXT_NS, "p") {
+                self.out.paragraph(&collapse_lines(&odt_inline(c)));
+            } else if is(c, TEXT_NS, "list") {
+                let style = c.attribute((TEXT_NS, "style-name")).unwrap_or("");
+                let mut lines = Vec::new();
+                self.list(c, style, 0, &mut lines);
+                for line in lines {
+                    self.out.list_item(&line);
+                }
+            } else if is(c, TABLE_NS, "table") {
+                self.out.block(&odt_table(c));
+            } else if is(c, TEXT_NS, "section") {
+                self.walk(c);
+            }
+        }
+    }
+
+    /// Пункты списка; вложенные списки наследуют стиль верхнего, если свой не задан.
+    /// `text:list-header` — пункт без номера, счётчик нумерации он не сдвигает.
+    fn list(&self, list: Node, style: &str, depth: usize, lines: &mut Vec<String>) {
+        let ordered = self
+            .list_styles
+            .get(style)
+            .and_then(|l| l.get(depth).copied())
+            .unwrap_or(false);
+        let items = list
+            .children()
+            .filter(|i| is(*i, TEXT_NS, "list-item") || is(*i, TEXT_NS, "list-header"));
+        let mut n = 0;
+        for item in items {
+            let numbered = is(item, TEXT_NS, "list-item");
+            if numbered {
+                n += 1;
+            }
+            for c in item.children().filter(|c| c.is_element()) {
+                if is(c, TEXT_NS, "list") {
+                    let nested = c.attribute((TEXT_NS, "style-name")).unwrap_or(style);
+                    self.list(c, nested, depth + 1, lines);
+                } else if is(c, TEXT_NS, "p") || is(c, TEXT_NS, "h") {
+                    let text = collapse_lines(&odt_inline(c)).replace('\n', " ");
+                    if !text.is_empty() {
+                        lines.push(list_line(depth, ordered && numbered, n, &text));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Текст абзаца ODT: `text:s` — пробелы, `text:tab`, `text:line-break`; сноски пропускаются.
+fn odt_inline(node: Node) -> String {
+    let mut s = String::new();
+    for c in node.children() {
+        if c.is_text() {
+            s.push_str(c.text().unwrap_or(""));
+            continue;
+        }
+        if !c.is_element() {
+            continue;
+        }
+        if c.tag_name().namespace() != Some(TEXT_NS) {
+            s.push_str(&odt_inline(c));
+            continue;
+        }
+        match c.tag_name().name() {
+            "s" | "tab" => s.push(' '),
+            "line-break" => s.push('\n'),
+            "note" | "bookmark" | "bookmark-start" | "bookmark-end" => {}
+            _ => s.push_str(&odt_inline(c)),
+        }
+    }
+    s
+}
+
+fn odt_table(table: Node) -> String {
+    let rows: Vec<Vec<String>> = table
+        .descendants()
+        .filter(|r| is(*r, TABLE_NS, "table-row"))
+        .map(|tr| {
+            tr.children()
+                .filter(|c| is(*c, TABLE_NS, "table-cell"))
+                .map(|tc| {
+                    let parts: Vec<String> = tc
+                        .children()
+                        .filter(|p| is(*p, TEXT_NS, "p") || is(*p, TEXT_NS, "h"))
+                        .map(|p| collapse_lines(&odt_inline(p)).replace('\n', " "))
+                        .filter(|t| !t.is_empty())
+                        .collect();
+                    parts.join(" ")
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|r: &Vec<String>| !r.is_empty())
+        .collect();
+    markdown_table(&rows)
+}
+
+/// meta.xml: dc:title, meta:initial-creator (или dc:creator), dc:date — дата изменения.
+fn odt_properties(doc: &Document) -> DocumentMeta {
+    let mut meta = DocumentMeta::default();
+    let Some(props) = doc.descendants().find(|n| n.tag_name().name() == "meta" && n.has_children()) else {
+        return meta;
+    };
+    let mut last_editor = None;
+    for n in props.children().filter(|n| n.is_element()) {
+        let value = n.text().unwrap_or("").trim();
+        if value.is_empty() {
+            continue;
+        }
+        match n.tag_name().name() {
+            "title" => meta.title = Some(value.to_string()),
+            "initial-creator" => meta.author = Some(value.to_string()),
+            "creator" => last_editor = Some(value.to_string()),
+            "keyword" => meta.tags.extend(split_keywords(value)),
+            "date" => insert_extra(&mut meta, "modified", value),
+            "creation-date" => insert_extra(&mut meta, "created", value),
+            "subject" => insert_extra(&mut meta, "subject", value),
+            "description" => insert_extra(&mut meta, "description", value),
+            _ => {}
+        }
+    }
+    if let Some(editor) = last_editor {
+        insert_extra(&mut meta, "last_modified_by", &editor);
+        meta.author.get_or_insert(editor);
+    }
+    meta
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunking::{parse_blocks, BlockKind};
+    use crate::extract::test_zip;
+
+    const DOCX_BODY: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Архитектура</w:t></w:r></w:p>
+<w:p><w:r><w:t xml:space="preserve">Сервис состоит из </w:t></w:r><w:r><w:t>трёх частей.</w:t></w:r></w:p>
+<w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>Ingest</w:t></w:r></w:p>
+<w:p><w:pPr><w:numPr><w:ilvl w:val="0"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>Query</w:t></w:r></w:p>
+<w:p><w:pPr><w:numPr><w:ilvl w:val="1"/><w:numId w:val="1"/></w:numPr></w:pPr><w:r><w:t>Rerank</w:t></w:r></w:p>
+<w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t>Лимиты</w:t></w:r></w:p>
+<w:tbl><w:tr><w:tc><w:p><w:r><w:t>Key</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>Value</w:t></w:r></w:p></w:tc></w:tr>
+<w:tr><w:tc><w:p><w:r><w:t>batch</w:t></w:r></w:p></w:tc><w:tc><w:p><w:r><w:t>64</w:t></w:r></w:p></w:tc></w:tr></w:tbl>
+<w:p><w:r><w:delText>deleted</w:delText></w:r></w:p>
+</w:body></w:document>"#;
+
+    const DOCX_STYLES: &str = r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/></w:style>
+<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/></w:style>
+</w:styles>"#;
+
+    const DOCX_NUMBERING: &str = r#"<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:abstractNum w:abstractNumId="7"><w:lvl w:ilvl="0"><w:numFmt w:val="decimal"/></w:lvl><w:lvl w:ilvl="1"><w:numFmt w:val="bullet"/></w:lvl></w:abstractNum>
+<w:num w:numId="1"><w:abstractNumId w:val="7"/></w:num>
+</w:numbering>"#;
+
+    const DOCX_CORE: &str = r#"<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/">
+<dc:title>Спецификация</dc:title><dc:creator>Alice</dc:creator><cp:keywords>rag, specs</cp:keywords>
+<dcterms:modified>2024-05-01T10:00:00Z</dcterms:modified></cp:coreProperties>"#;
+
+    #[test]
+    fn test_docx_structure_and_properties() {
+        let bytes = test_zip(&[
+            ("word/document.xml", DOCX_BODY.as_bytes()),
+            ("word/styles.xml", DOCX_STYLES.as_bytes()),
+            ("word/numbering.xml", DOCX_NUMBERING.as_bytes()),
+            ("docProps/core.xml", DOCX_CORE.as_bytes()),
+        ]);
+        let ex = extract_docx(&bytes).unwrap();
+
+        assert_eq!(
+            ex.text,
+            "# Архитектура\n\nСервис состоит из трёх частей.\n\n1. Ingest\n2. Query\n  - Rerank\n\n\
+             ## Лимиты\n\n| Key | Value |\n| --- | --- |\n| batch | 64 |"
+        );
+        let kinds: Vec<BlockKind> = parse_blocks(&ex.text).iter().map(|b| b.kind).collect();
+        assert!(kinds.contains(&BlockKind::Table));
+        assert!(kinds.contains(&BlockKind::List));
+
+        assert_eq!(ex.meta.title.as_deref(), Some("Спецификация"));
+        assert_eq!(ex.meta.author.as_deref(), Some("Alice"));
+        assert_eq!(ex.meta.tags, vec!["rag", "specs"]);
+        assert_eq!(ex.meta.extra["modified"], "2024-05-01T10:00:00Z");
+    }
+
+    #[test]
+    fn test_docx_styles_based_on() {
+        let styles = r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/></w:style>
+<w:style w:type="paragraph" w:styleId="MyHeading"><w:name w:val="My Heading"/><w:basedOn w:val="Heading2"/></w:style>
+<w:style w:type="paragraph" w:styleId="ListBase"><w:name w:val="List Base"/><w:pPr><w:numPr><w:numId w:val="1"/></w:numPr></w:pPr></w:style>
+<w:style w:type="paragraph" w:styleId="MyList"><w:name w:val="My List"/><w:basedOn w:val="ListBase"/></w:style>
+<w:style w:type="paragraph" w:styleId="NoList"><w:name w:val="No List"/><w:basedOn w:val="MyList"/><w:pPr><w:numPr><w:numId w:val="0"/></w:numPr></w:pPr></w:style>
+<w:style w:type="paragraph" w:styleId="LoopA"><w:name w:val="Loop A"/><w:basedOn w:val="LoopB"/></w:style>
+<w:style w:type="paragraph" w:styleId="LoopB"><w:name w:val="Loop B"/><w:basedOn w:val="LoopA"/></w:style>
+</w:styles>"#;
+        let styles = word_styles(&parse_xml(styles).unwrap());
+        assert_eq!(styles["MyHeading"].heading, Some(2));
+        assert_eq!(styles["MyList"].numbering, Some(("1".to_string(), 0)));
+        assert_eq!(styles["NoList"].numbering, None);
+        assert_eq!(styles["LoopA"].heading, None);
+    }
+
+    #[test]
+    fn test_docx_wrapped_body_content() {
+        let body = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+<w:customXml w:element="clause"><w:p><w:r><w:t>Custom XML.</w:t></w:r></w:p></w:customXml>
+<w:ins w:id="1" w:author="A"><w:p><w:r><w:t>Inserted.</w:t></w:r></w:p></w:ins>
+<w:smartTag w:element="place"><w:p><w:r><w:t>Smart tag.</w:t></w:r></w:p></w:smartTag>
+<w:sdt><w:sdtContent><w:tbl><w:tr><w:tc><w:p><w:r><w:t>cell</w:t></w:r></w:p></w:tc></w:tr></w:tbl></w:sdtContent></w:sdt>
+<w:del w:id="2" w:author="A"><w:p><w:r><w:t>Deleted.</w:t></w:r></w:p></w:del>
+</w:body></w:document>"#;
+        let ex = extract_docx(&test_zip(&[("word/document.xml", body.as_bytes())])).unwrap();
+        assert_eq!(ex.text, "Custom XML.\n\nInserted.\n\nSmart tag.\n\n| cell |\n| --- |");
+    }
+
+    #[test]
+    fn test_plain_paragraphs_are_escaped() {
+        let body = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+<w:p><w:r><w:t># not a heading</w:t></w:r></w:p>
+<w:p><w:r><w:t>1. not a list</w:t><w:br/><w:t>```</w:t></w:r></w:p>
+<w:p><w:r><w:t>Tail text.</w:t></w:r></w:p>
+</w:body></w:document>"#;
+        let ex = extract_docx(&test_zip(&[("word/document.xml", body.as_bytes())])).unwrap();
+        assert_eq!(ex.text, "\\# not a heading\n\n1\\. not a list\n\\```\n\nTail text.");
+        let kinds: Vec<BlockKind> = parse_blocks(&ex.text).iter().map(|b| b.kind).collect();
+        assert_eq!(kinds, vec![BlockKind::Paragraph; 3]);
+
+        let content = r#"<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0">
+<office:body><office:text><text:p>- dash first</text:p></office:text></office:body></office:document-content>"#;
+        let ex = extract_odt(&test_zip(&[("content.xml", content.as_bytes())])).unwrap();
+        assert_eq!(ex.text, "\\- dash first");
+    }
+
+    #[test]
+    fn test_odt_structure_and_properties() {
+        let content = r#"<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0">
+<office:automatic-styles><text:list-style style:name="L1"><text:list-level-style-number text:level="1"/></text:list-style></office:automatic-styles>
+<office:body><office:text>
+<text:h text:outline-level="2">Обзор</text:h>
+<text:p>Первый<text:s/>абзац<text:note><text:note-body><text:p>сноска</text:p></text:note-body></text:note>.</text:p>
+<text:list text:style-name="L1"><text:list-header><text:p>Порядок</text:p></text:list-header><text:list-item><text:p>Шаг один</text:p></text:list-item><text:list-item><text:p>Шаг два</text:p></text:list-item></text:list>
+<table:table><table:table-row><table:table-cell><text:p>A</text:p></table:table-cell><table:table-cell><text:p>B</text:p></table:table-cell></table:table-row></table:table>
+</office:text></office:body></office:document-content>"#;
+        let meta = r#"<office:document-meta xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:meta="urn:oasis:names:tc:opendocument:xmlns:meta:1.0" xmlns:dc="http://purl.org/dc/elements/1.1/">
+<office:meta><dc:title>Заметки</dc:title><meta:initial-creator>Bob</meta:initial-creator><dc:creator>Carol</dc:creator><dc:date>2024-06-02T08:00:00</dc:date></office:meta></office:document-meta>"#;
+        let ex = extract_odt(&test_zip(&[("content.xml", content.as_bytes()), ("meta.xml", meta.as_bytes())])).unwrap();
+
+        assert_eq!(
+            ex.text,
+            "## Обзор\n\nПервый абзац.\n\n- Порядок\n1. Шаг один\n2. Шаг два\n\n| A | B |\n| --- | --- |"
+        );
+        assert_eq!(ex.meta.title.as_deref(), Some("Заметки"));
+        assert_eq!(ex.meta.author.as_deref(), Some("Bob"));
+        assert_eq!(ex.meta.extra["last_modified_by"], "Carol");
+        assert_eq!(ex.meta.extra["modified"], "2024-06-02T08:00:00");
+    }
+}
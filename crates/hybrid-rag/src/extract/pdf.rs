@@ -0,0 +1,254 @@
+// file: src/extract/pdf.rs
+//
+// PDF → текст по страницам (pdf-extract, чистый Rust). Повторяющиеся
+// колонтитулы и номера страниц выкидываются, каждая страница — `Region`
+// с полями `page`/`page_end`.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::{Extracted, Region};
+
+lazy_static! {
+    static ref RE_DIGITS: Regex = Regex::new(r"\d+").unwrap();
+    /// "12", "- 12 -", "Page 3 of 10", "Стр. 4", "iv"
+    static ref RE_PAGE_NUMBER: Regex = Regex::new(
+        r"(?i)^[\s\-–—]*(?:(?:page|стр\.?|страница|p\.)\s*)?(\d+|[ivx]+)(?:\s*(?:of|из|/)\s*\d+)?[\s\-–—]*$"
+    )
+    .unwrap();
+    /// Римское число до 39 в каноническом виде ("iv", но не "iiii" и не "vx")
+    static ref RE_ROMAN: Regex = Regex::new(r"(?i)^(x{0,3})(ix|iv|v?i{0,3})$").unwrap();
+    static ref RE_HYPHEN_BREAK: Regex = Regex::new(r"(\p{L})-\n(\p{Ll})").unwrap();
+    static ref RE_MANY_BLANKS: Regex = Regex::new(r"\n{3,}").unwrap();
+}
+
+/// Сколько строк сверху и снизу страницы проверять на колонтитулы.
+const EDGE_LINES: usize = 2;
+
+/// Текст PDF; страницы разделены пустой строкой, `regions` — по странице на участок.
+pub fn extract_pdf(bytes: &[u8]) -> Result<Extracted> {
+    // pdf-extract panics on some malformed files instead of returning an error
+    let pages = std::panic::catch_unwind(|| pdf_extract::extract_text_from_mem_by_pages(bytes))
+        .map_err(|_| anyhow!("PDF parser panicked on malformed input"))?
+        .map_err(|e| anyhow!("failed to extract PDF text: {}", e))?;
+
+    let pages = strip_page_furniture(&pages);
+    let mut out = Extracted::default();
+    for (i, page) in pages.iter().enumerate() {
+        let page = clean_page(page);
+        if page.is_empty() {
+            continue;
+        }
+        if !out.text.is_empty() {
+            out.text.push_str("\n\n");
+        }
+        let start = out.text.len();
+        out.text.push_str(&page);
+        let number = serde_json::Value::from(i + 1);
+        out.regions.push(Region {
+            start,
+            end: out.text.len(),
+            fields: [("page".to_string(), number.clone()), ("page_end".to_string(), number)]
+                .into_iter()
+                .collect(),
+        });
+    }
+    if out.text.is_empty() {
+        return Err(anyhow!("PDF has no extractable text (scanned images?)"));
+    }
+    Ok(out)
+}
+
+/// Убирает номера страниц и строки, повторяющиеся сверху/снизу на многих страницах
+/// (цифры при сравнении не учитываются: "Report — page 3" == "Report — page 4").
+/// Номер страницы — строка-счётчик, которая хотя бы на двух страницах идёт
+/// в ногу с их порядком ("iii", "iv", "v"), так что одиночное "I" или "vi" остаётся.
+fn strip_page_furniture(pages: &[String]) -> Vec<String> {
+    let lines: Vec<Vec<&str>> = pages.iter().map(|p| p.lines().collect()).collect();
+    let edges = |page: &[&str]| -> Vec<usize> {
+        let content: Vec<usize> = (0..page.len()).filter(|&i| !page[i].trim().is_empty()).collect();
+        let mut idx: Vec<usize> = content.iter().take(EDGE_LINES).copied().collect();
+        idx.extend(content.iter().rev().take(EDGE_LINES));
+        idx.sort_unstable();
+        idx.dedup();
+        idx
+    };
+
+    // How many pages have each normalized line at their top/bottom edge
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for page in &lines {
+        let mut seen: Vec<String> = edges(page).into_iter().map(|i| normalize(page[i])).collect();
+        seen.sort();
+        seen.dedup();
+        for key in seen {
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+    let min_repeats = (pages.len() / 2).max(2);
+
+    // Pages per counter: (line shape, number minus page index)
+    let mut counters: HashMap<(String, i64), usize> = HashMap::new();
+    for (index, page) in lines.iter().enumerate() {
+        let mut seen: Vec<(String, i64)> = edges(page)
+            .into_iter()
+            .filter_map(|i| page_counter(page[i], index))
+            .collect();
+        seen.sort();
+        seen.dedup();
+        for key in seen {
+            *counters.entry(key).or_default() += 1;
+        }
+    }
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, page)| {
+            let drop: Vec<usize> = edges(page)
+                .into_iter()
+                .filter(|&i| {
+                    page_counter(page[i], index).is_some_and(|key| counters[&key] >= 2)
+                        || (pages.len() >= 3 && counts[&normalize(page[i])] >= min_repeats)
+                })
+                .collect();
+            page.iter()
+                .enumerate()
+                .filter(|(i, _)| !drop.contains(i))
+                .map(|(_, l)| *l)
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect()
+}
+
+/// Строка номера страницы → (её вид с `#` вместо номера, номер минус индекс страницы).
+fn page_counter(line: &str, index: usize) -> Option<(String, i64)> {
+    let caps = RE_PAGE_NUMBER.captures(line)?;
+    let number = caps.get(1)?;
+    let value = match number.as_str().parse::<i64>() {
+        Ok(n) => n,
+        Err(_) => roman_value(number.as_str())?,
+    };
+    let shape = format!("{}#{}", &line[..number.start()], &line[number.end()..]);
+    Some((normalize(&shape), value - index as i64))
+}
+
+fn roman_value(s: &str) -> Option<i64> {
+    let caps = RE_ROMAN.captures(s)?;
+    let tens = caps[1].len() as i64 * 10;
+    let units = match caps[2].to_ascii_lowercase().as_str() {
+        "ix" => 9,
+        "iv" => 4,
+        u => u.strip_prefix('v').map_or(0, |_| 5) + u.matches('i').count() as i64,
+    };
+    Some(tens + units).filter(|&n| n > 0)
+}
+
+fn normalize(line: &str) -> String {
+    RE_DIGITS
+        .replace_all(&line.split_whitespace().collect::<Vec<_>>().join(" "), "#")
+        .to_lowercase()
+}
+
+/// Хвостовые пробелы, переносы слов на границе строк, лишние пустые строки.
+fn clean_page(page: &str) -> String {
+    let text: String = page.lines().map(str::trim_end).collect::<Vec<_>>().join("\n");
+    let text = RE_HYPHEN_BREAK.replace_all(&text, "$1$2");
+    let text = RE_MANY_BLANKS.replace_all(&text, "\n\n");
+    text.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::{region_fields, sniff_content, ContentType};
+
+    /// Minimal PDF: one Helvetica text line per entry, one page per Vec.
+    fn make_pdf(pages: &[Vec<&str>]) -> Vec<u8> {
+        let n = pages.len();
+        let kids: Vec<String> = (0..n).map(|i| format!("{} 0 R", 4 + 2 * i)).collect();
+        let mut objs = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), n),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+        ];
+        for (i, lines) in pages.iter().enumerate() {
+            objs.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] \
+                 /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                5 + 2 * i
+            ));
+            let mut content = String::from("BT /F1 12 Tf 14 TL 72 740 Td\n");
+            for l in lines {
+                content.push_str(&format!("({}) Tj T*\n", l));
+            }
+            content.push_str("ET");
+            objs.push(format!("<< /Length {} >>\nstream\n{}\nendstream", content.len(), content));
+        }
+
+        let mut out = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, o) in objs.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, o).as_bytes());
+        }
+        let xref = out.len();
+        out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objs.len() + 1).as_bytes());
+        for off in offsets {
+            out.extend(format!("{:010} 00000 n \n", off).as_bytes());
+        }
+        out.extend(
+            format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objs.len() + 1, xref)
+                .as_bytes(),
+        );
+        out
+    }
+
+    #[test]
+    fn test_pages_and_furniture() {
+        let pdf = make_pdf(&[
+            vec!["ACME Annual Report 2024", "Revenue grew in every", "region this year.", "", "- 1 -"],
+            vec!["ACME Annual Report 2024", "Costs were flat.", "- 2 -"],
+            vec!["ACME Annual Report 2024", "Outlook is positive.", "- 3 -"],
+        ]);
+        assert_eq!(sniff_content("report.bin", &pdf), ContentType::Pdf);
+        let ex = extract_pdf(&pdf).unwrap();
+
+        assert!(!ex.text.contains("ACME"), "{}", ex.text);
+        assert!(!ex.text.contains("- 2 -"), "{}", ex.text);
+        assert!(ex.text.contains("Revenue grew in every\nregion this year."));
+        assert_eq!(ex.regions.len(), 3);
+
+        let second = ex.text.find("Costs").unwrap();
+        assert_eq!(&ex.text[ex.regions[1].start..ex.regions[1].end], "Costs were flat.");
+        let fields = region_fields(&ex.regions, second, ex.text.len());
+        assert_eq!(fields["page"], 2);
+        assert_eq!(fields["page_end"], 3);
+    }
+
+    #[test]
+    fn test_page_counters_only() {
+        let pages: Vec<String> = [
+            "Preface text.\nCivil\niii",
+            "Second page.\nI\niv",
+            "Third page.\nvi\nv",
+        ]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+        let stripped = strip_page_furniture(&pages);
+        // Roman page numbers go; words and stray numerals at the edges stay
+        assert_eq!(stripped, vec!["Preface text.\nCivil", "Second page.\nI", "Third page.\nvi"]);
+        assert_eq!(roman_value("XXXIX"), Some(39));
+        assert_eq!(roman_value("civil"), None);
+        assert_eq!(roman_value("iiii"), None);
+    }
+
+    #[test]
+    fn test_garbage_is_error() {
+        assert!(extract_pdf(b"%PDF-1.4\nnot really a pdf").is_err());
+    }
+}
@@ -0,0 +1,224 @@
+// file: src/extract/records.rs
+//
+// Табличные/структурные данные по записям: строка CSV/TSV, строка JSONL или
+// элемент JSON-массива — отдельный документ. Какие поля идут в текст, какие
+// в метаданные и какое поле — стабильный id, задаёт `RecordMapping`.
+
+use anyhow::{anyhow, Context, Result};
+use serde_json::{Map, Value};
+
+use super::extension;
+use crate::chunking::{meta_from_fields, value_to_string, DocumentMeta};
+use crate::config::RecordMapping;
+
+/// Одна запись, готовая к индексации как отдельный документ.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Record {
+    /// Значение `id_field` (если задано и заполнено)
+    pub id: Option<String>,
+    pub text: String,
+    pub meta: DocumentMeta,
+}
+
+/// Табличный ли это формат (по расширению); по записям он индексируется,
+/// только если включён `RecordMapping`.
+pub fn is_record_format(name: &str) -> bool {
+    matches!(extension(name).as_str(), "csv" | "tsv" | "jsonl" | "ndjson" | "json")
+}
+
+/// Записи файла. `None` — не записи: маппинг не включён, другое расширение
+/// или JSON-объект верхнего уровня (конфиг, а не набор данных) — индексируется как текст.
+pub fn parse_records(name: &str, text: &str, mapping: &RecordMapping) -> Result<Option<Vec<Record>>> {
+    if !mapping.is_enabled() {
+        return Ok(None);
+    }
+    let text = text.trim_start_matches('\u{FEFF}');
+    let rows: Vec<Row> = match extension(name).as_str() {
+        "csv" => csv_rows(text, None)?,
+        "tsv" => csv_rows(text, Some(b'\t'))?,
+        "jsonl" | "ndjson" => jsonl_rows(text)?.into_iter().map(Row::from).collect(),
+        "json" => match serde_json::from_str::<Value>(text).context("invalid JSON")? {
+            Value::Array(items) => items.into_iter().map(Row::from).collect(),
+            _ => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(
+        rows.into_iter()
+            .filter_map(|row| record_from_row(row, mapping))
+            .collect(),
+    ))
+}
+
+/// Поля записи в исходном порядке (колонки CSV) или скаляр из JSON-массива.
+enum Row {
+    Fields(Vec<(String, Value)>),
+    Scalar(Value),
+}
+
+impl From<Value> for Row {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Object(fields) => Row::Fields(fields.into_iter().collect()),
+            other => Row::Scalar(other),
+        }
+    }
+}
+
+fn csv_rows(text: &str, delimiter: Option<u8>) -> Result<Vec<Row>> {
+    let delimiter = delimiter.unwrap_or_else(|| sniff_delimiter(text));
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .context("CSV has no header row")?
+        .iter()
+        .map(|h| h.trim().to_string())
+        .collect();
+
+    let mut rows = Vec::new();
+    for (i, row) in reader.records().enumerate() {
+        let row = row.with_context(|| format!("CSV row {}", i + 2))?;
+        let fields: Vec<(String, Value)> = headers
+            .iter()
+            .zip(row.iter())
+            .filter(|(h, v)| !h.is_empty() && !v.trim().is_empty())
+            .map(|(h, v)| (h.clone(), Value::from(v.trim())))
+            .collect();
+        rows.push(Row::Fields(fields));
+    }
+    Ok(rows)
+}
+
+/// `,`, `;` или табуляция — что чаще встречается в строке заголовка.
+fn sniff_delimiter(text: &str) -> u8 {
+    let header = text.lines().next().unwrap_or("");
+    [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|d| header.bytes().filter(|b| b == d).count())
+        .filter(|d| header.as_bytes().contains(d))
+        .unwrap_or(b',')
+}
+
+fn jsonl_rows(text: &str) -> Result<Vec<Value>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| serde_json::from_str(l).map_err(|e| anyhow!("JSONL line {}: {}", i + 1, e)))
+        .collect()
+}
+
+/// Запись по маппингу; `None`, если текст пустой.
+fn record_from_row(row: Row, mapping: &RecordMapping) -> Option<Record> {
+    let fields = match row {
+        Row::Fields(fields) => fields,
+        // Bare strings/numbers in a JSON array are the text themselves
+        Row::Scalar(value) => {
+            let text = value_to_string(&value)?;
+            return (!text.is_empty()).then(|| Record { text, ..Default::default() });
+        }
+    };
+    let get = |name: &str| fields.iter().find(|(k, _)| k == name);
+
+    let id = mapping
+        .id_field
+        .as_deref()
+        .and_then(get)
+        .and_then(|(_, v)| value_to_string(v))
+        .filter(|id| !id.is_empty());
+    let meta_fields: Map<String, Value> = fields
+        .iter()
+        .filter(|(k, _)| mapping.meta_fields.contains(k))
+        .cloned()
+        .collect();
+
+    // Explicit text fields keep the mapping's order; otherwise every field that
+    // is neither the id nor metadata, in record order
+    let text_fields: Vec<&(String, Value)> = if mapping.text_fields.is_empty() {
+        fields
+            .iter()
+            .filter(|(k, _)| Some(k.as_str()) != mapping.id_field.as_deref() && !meta_fields.contains_key(k))
+            .collect()
+    } else {
+        mapping.text_fields.iter().filter_map(|f| get(f)).collect()
+    };
+    let parts: Vec<(&String, String)> = text_fields
+        .into_iter()
+        .filter_map(|(k, v)| Some((k, value_to_string(v)?)))
+        .filter(|(_, v)| !v.is_empty())
+        .collect();
+    let text = match parts.as_slice() {
+        [] => return None,
+        [(_, value)] => value.clone(),
+        _ => parts
+            .iter()
+            .map(|(k, v)| format!("{}: {}", k, v))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+
+    let mut meta = meta_from_fields(&meta_fields);
+    if let Some(id) = &id {
+        meta.extra.insert("record_id".into(), Value::from(id.clone()));
+    }
+    Some(Record { id, text, meta })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mapping(text: &[&str], meta: &[&str], id: Option<&str>) -> RecordMapping {
+        RecordMapping {
+            enabled: true,
+            text_fields: text.iter().map(|s| s.to_string()).collect(),
+            meta_fields: meta.iter().map(|s| s.to_string()).collect(),
+            id_field: id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_csv_rows_with_mapping() {
+        let csv = "\u{FEFF}sku;title;description;price\n\
+                   A-1;Кофемолка;Жернова из стали;4990\n\
+                   A-2;Чайник;;1990\n\
+                   A-3;;;\n";
+        let m = mapping(&["title", "description"], &["price", "title"], Some("sku"));
+        let records = parse_records("goods.csv", csv, &m).unwrap().unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].id.as_deref(), Some("A-1"));
+        assert_eq!(records[0].text, "title: Кофемолка\ndescription: Жернова из стали");
+        assert_eq!(records[0].meta.title.as_deref(), Some("Кофемолка"));
+        assert_eq!(records[0].meta.extra["price"], "4990");
+        assert_eq!(records[0].meta.extra["record_id"], "A-1");
+        // Single non-empty text field: the value alone
+        assert_eq!(records[1].text, "Чайник");
+    }
+
+    #[test]
+    fn test_json_and_jsonl() {
+        let m = mapping(&[], &["author"], Some("id"));
+        let jsonl = "{\"id\": 7, \"body\": \"first\", \"author\": \"Ann\"}\n\n{\"id\": 8, \"body\": \"second\"}\n";
+        let records = parse_records("log.jsonl", jsonl, &m).unwrap().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].text, "first");
+        assert_eq!(records[0].id.as_deref(), Some("7"));
+        assert_eq!(records[0].meta.author.as_deref(), Some("Ann"));
+
+        let json = r#"[{"id": "x", "q": "Why?", "a": "Because."}, "plain string"]"#;
+        let records = parse_records("faq.json", json, &m).unwrap().unwrap();
+        // JSON object keys come in sorted order (serde_json without preserve_order)
+        assert_eq!(records[0].text, "a: Because.\nq: Why?");
+        assert_eq!(records[1].text, "plain string");
+
+        // A top-level object is a document, not a dataset
+        assert!(parse_records("config.json", r#"{"a": 1}"#, &m).unwrap().is_none());
+        assert!(parse_records("bad.jsonl", "{\"a\": 1}\nnope", &m).is_err());
+
+        // Without a mapping or the flag a CSV is plain text
+        assert!(parse_records("goods.csv", "a,b\n1,2\n", &RecordMapping::default()).unwrap().is_none());
+    }
+}
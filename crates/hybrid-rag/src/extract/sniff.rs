@@ -0,0 +1,363 @@
+// file: src/extract/sniff.rs
+//
+// Определение типа загруженного файла по содержимому: сигнатуры (magic bytes),
+// BOM и эвристики кодировки. Расширение — только подсказка для неоднозначных
+// случаев (zip без маркеров DOCX/ODT/EPUB), так что переименованный PDF или
+// картинка с `.txt` не индексируются как мусор.
+
+use std::io::Read;
+
+use anyhow::{bail, Context, Result};
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use flate2::read::GzDecoder;
+
+use super::office::{open_zip, read_part};
+use super::{extension, looks_binary, ArchiveKind};
+
+/// Что лежит в файле и каким экстрактором его читать.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentType {
+    Pdf,
+    Docx,
+    Odt,
+    Epub,
+    Archive(ArchiveKind),
+    /// Один сжатый файл (`notes.txt.gz`), не tar
+    Gzip,
+    /// Текст в любой кодировке, см. `decode_to_utf8_hard`
+    Text,
+    /// Формат без экстрактора (картинка, исполняемый файл, XLSX…)
+    Unsupported(&'static str),
+}
+
+/// Файл такого типа не индексируется.
+#[derive(Debug, thiserror::Error)]
+#[error("unsupported file type: {what} ({name})")]
+pub struct UnsupportedContent {
+    pub name: String,
+    pub what: &'static str,
+}
+
+/// Сигнатуры форматов, которые индексировать нечем. Сигнатуры из печатных
+/// символов (`ID3`, `BZh`, `RIFF`…) проверяются отдельно в `text_like_magic`.
+const UNSUPPORTED_MAGIC: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "PNG image"),
+    (b"\xff\xd8\xff", "JPEG image"),
+    (b"GIF87a", "GIF image"),
+    (b"GIF89a", "GIF image"),
+    (b"II*\x00", "TIFF image"),
+    (b"MM\x00*", "TIFF image"),
+    (b"\x7fELF", "ELF executable"),
+    (b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1", "legacy MS Office document (DOC/XLS/PPT)"),
+    (b"7z\xbc\xaf\x27\x1c", "7-Zip archive"),
+    (b"Rar!\x1a\x07", "RAR archive"),
+    (b"\xfd7zXZ\x00", "xz archive"),
+    (b"\x28\xb5\x2f\xfd", "zstd archive"),
+    (b"SQLite format 3\x00", "SQLite database"),
+    (b"\x1a\x45\xdf\xa3", "Matroska/WebM video"),
+];
+
+/// Сигнатуры, с которых может начинаться и обычный текст («ID3 tags…»):
+/// засчитываются только вместе с полями заголовка, которые за ними следуют.
+fn text_like_magic(bytes: &[u8]) -> Option<&'static str> {
+    let at = |range: std::ops::Range<usize>| bytes.get(range).unwrap_or_default();
+    if bytes.starts_with(b"ID3") && matches!(bytes.get(3), Some(2..=4)) {
+        return Some("MP3 audio");
+    }
+    if bytes.starts_with(b"BZh")
+        && matches!(bytes.get(3), Some(b'1'..=b'9'))
+        && matches!(at(4..10), b"1AY&SY" | b"\x17rE8P\x90")
+    {
+        return Some("bzip2 archive");
+    }
+    if bytes.starts_with(b"RIFF") && matches!(at(8..12), b"WAVE" | b"AVI " | b"WEBP") {
+        return Some("RIFF media (WAV/AVI/WebP)");
+    }
+    if bytes.starts_with(b"OggS") && bytes.get(4) == Some(&0) {
+        return Some("Ogg media");
+    }
+    // STREAMINFO is always the first metadata block and is 34 bytes long
+    if bytes.starts_with(b"fLaC")
+        && bytes.get(4).is_some_and(|b| b & 0x7f == 0)
+        && at(5..8) == b"\x00\x00\x22"
+    {
+        return Some("FLAC audio");
+    }
+    if bytes.starts_with(b"wOFF") && matches!(at(4..8), b"\x00\x01\x00\x00" | b"OTTO" | b"true") {
+        return Some("web font");
+    }
+    None
+}
+
+/// Тип содержимого; `name` нужен только для zip без явных маркеров.
+pub fn sniff_content(name: &str, bytes: &[u8]) -> ContentType {
+    if bytes.starts_with(b"%PDF-") {
+        return ContentType::Pdf;
+    }
+    if bytes.starts_with(b"PK\x03\x04") || bytes.starts_with(b"PK\x05\x06") {
+        return sniff_zip(name, bytes);
+    }
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        // Peek inside: a tarball has "ustar" at offset 257 of the stream
+        let mut head = Vec::new();
+        let _ = GzDecoder::new(bytes).take(512).read_to_end(&mut head);
+        return if head.get(257..262) == Some(b"ustar") {
+            ContentType::Archive(ArchiveKind::TarGz)
+        } else {
+            ContentType::Gzip
+        };
+    }
+    if bytes.get(257..262) == Some(b"ustar") {
+        return ContentType::Archive(ArchiveKind::Tar);
+    }
+    if bytes.get(4..8) == Some(b"ftyp") {
+        return ContentType::Unsupported("MP4/QuickTime media");
+    }
+    if let Some(&(_, what)) = UNSUPPORTED_MAGIC.iter().find(|(magic, _)| bytes.starts_with(magic)) {
+        return ContentType::Unsupported(what);
+    }
+    if let Some(what) = text_like_magic(bytes) {
+        return ContentType::Unsupported(what);
+    }
+
+    // Text: a BOM or UTF-16 settle it, otherwise control bytes give binaries away
+    let sample = &bytes[..bytes.len().min(8192)];
+    if Encoding::for_bom(bytes).is_some() || utf16_without_bom(sample).is_some() {
+        return ContentType::Text;
+    }
+    if looks_binary(bytes) || too_many_controls(sample) {
+        return ContentType::Unsupported("binary data");
+    }
+    ContentType::Text
+}
+
+/// Управляющих байтов (кроме пробельных и ESC) больше 10%.
+fn too_many_controls(sample: &[u8]) -> bool {
+    let control = sample
+        .iter()
+        .filter(|&&b| (b < 0x09 || (b > 0x0D && b < 0x20)) && b != 0x1B)
+        .count();
+    control * 10 > sample.len()
+}
+
+/// DOCX/ODT/EPUB — тоже zip: различаем по содержимому, иначе по расширению.
+fn sniff_zip(name: &str, bytes: &[u8]) -> ContentType {
+    let by_extension = match extension(name).as_str() {
+        "docx" | "docm" | "dotx" => ContentType::Docx,
+        "odt" | "ott" => ContentType::Odt,
+        "epub" => ContentType::Epub,
+        _ => ContentType::Archive(ArchiveKind::Zip),
+    };
+    let Ok(mut zip) = open_zip(bytes) else {
+        return by_extension;
+    };
+    // ODF and EPUB store their media type in an uncompressed `mimetype` entry
+    if let Ok(Some(mimetype)) = read_part(&mut zip, "mimetype") {
+        match mimetype.trim() {
+            "application/epub+zip" => return ContentType::Epub,
+            "application/vnd.oasis.opendocument.text" | "application/vnd.oasis.opendocument.text-template" => {
+                return ContentType::Odt
+            }
+            m if m.starts_with("application/vnd.oasis.opendocument.") => {
+                return ContentType::Unsupported("OpenDocument file other than text (ODS/ODP/ODG)")
+            }
+            _ => {}
+        }
+    }
+    if zip.index_for_name("[Content_Types].xml").is_some() {
+        if zip.index_for_name("word/document.xml").is_some() {
+            return ContentType::Docx;
+        }
+        if zip.index_for_name("xl/workbook.xml").is_some() {
+            return ContentType::Unsupported("Excel workbook (XLSX)");
+        }
+        if zip.index_for_name("ppt/presentation.xml").is_some() {
+            return ContentType::Unsupported("PowerPoint presentation (PPTX)");
+        }
+    }
+    by_extension
+}
+
+/// UTF-16 без BOM. Обычный UTF-8 текст отсекается сразу (ASCII — тоже
+/// валидный UTF-16, только из иероглифов); дальше старшие байты почти всех
+/// кодовых единиц — 0x00 и один блок алфавита (0x04 — кириллица), а сэмпл
+/// декодируется без ошибок и почти без управляющих символов.
+fn utf16_without_bom(sample: &[u8]) -> Option<&'static Encoding> {
+    let utf8 = match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    if sample.len() < 4 || (utf8 && !sample.contains(&0) && !too_many_controls(sample)) {
+        return None;
+    }
+    [UTF_16LE, UTF_16BE]
+        .into_iter()
+        .find(|&enc| is_utf16(sample, enc == UTF_16LE))
+}
+
+fn is_utf16(sample: &[u8], little_endian: bool) -> bool {
+    let units: Vec<u16> = sample
+        .chunks_exact(2)
+        .map(|p| if little_endian { u16::from_le_bytes([p[0], p[1]]) } else { u16::from_be_bytes([p[0], p[1]]) })
+        .collect();
+    let mut high = [0usize; 256];
+    for unit in &units {
+        high[usize::from(unit >> 8)] += 1;
+    }
+    let script = high[1..].iter().max().copied().unwrap_or(0);
+    if (high[0] + script) * 10 < units.len() * 9 {
+        return false;
+    }
+
+    let (mut chars, mut control) = (0, 0);
+    let mut decoded = char::decode_utf16(units).peekable();
+    while let Some(c) = decoded.next() {
+        match c {
+            Ok(c) => {
+                chars += 1;
+                if c.is_control() && !matches!(c, '\t' | '\n' | '\r') {
+                    control += 1;
+                }
+            }
+            // A surrogate pair cut by the end of the sample
+            Err(_) if decoded.peek().is_none() => {}
+            Err(_) => return false,
+        }
+    }
+    chars > 0 && control * 100 <= chars
+}
+
+/// Кодировка текста: BOM, UTF-16 без BOM (NUL и управляющие байты — валидный
+/// UTF-8, поэтому раньше), UTF-8 (в т.ч. обрезанный посреди символа), иначе догадка chardetng.
+pub fn text_encoding(bytes: &[u8]) -> &'static Encoding {
+    if let Some((enc, _)) = Encoding::for_bom(bytes) {
+        return enc;
+    }
+    if let Some(enc) = utf16_without_bom(&bytes[..bytes.len().min(8192)]) {
+        return enc;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return UTF_8,
+        Err(e) if e.error_len().is_none() => return UTF_8,
+        Err(_) => {}
+    }
+    let mut det = EncodingDetector::new();
+    det.feed(bytes, true);
+    det.guess(None, true)
+}
+
+/// Жёсткая декодировка любых байтов в UTF-8 (кодировка — `text_encoding`),
+/// BOM отбрасывается.
+pub fn decode_to_utf8_hard(bytes: &[u8]) -> String {
+    let (cow, _, _) = text_encoding(bytes).decode(bytes);
+    cow.into_owned()
+}
+
+/// Распаковать одиночный gzip, не больше `limit` байт.
+pub fn gunzip(bytes: &[u8], limit: u64) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    GzDecoder::new(bytes)
+        .take(limit + 1)
+        .read_to_end(&mut out)
+        .context("invalid gzip data")?;
+    if out.len() as u64 > limit {
+        bail!("gzip unpacks to more than {} bytes", limit);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::test_zip;
+    use std::io::Write;
+
+    #[test]
+    fn test_sniff_by_magic_not_extension() {
+        assert_eq!(sniff_content("scan.txt", b"%PDF-1.7\n..."), ContentType::Pdf);
+        assert_eq!(
+            sniff_content("notes.txt", b"\x89PNG\r\n\x1a\n\x00\x00"),
+            ContentType::Unsupported("PNG image")
+        );
+        assert_eq!(sniff_content("data.csv", b"\x00\x01\x02garbage"), ContentType::Unsupported("binary data"));
+        assert_eq!(
+            sniff_content("dump.bin", b"\x01\x02\x03\x04\x05\x06\x07\x08\x0e\x0f"),
+            ContentType::Unsupported("binary data")
+        );
+        assert_eq!(sniff_content("hello", b"hello"), ContentType::Text);
+        assert_eq!(
+            sniff_content("song.txt", b"ID3\x03\x00\x00\x00\x00\x00\x00"),
+            ContentType::Unsupported("MP3 audio")
+        );
+        assert_eq!(
+            sniff_content("data.txt", b"BZh91AY&SY\x12\x34"),
+            ContentType::Unsupported("bzip2 archive")
+        );
+        assert_eq!(
+            sniff_content("clip.txt", b"RIFF\x24\x08\x00\x00WAVEfmt "),
+            ContentType::Unsupported("RIFF media (WAV/AVI/WebP)")
+        );
+
+        let docx = test_zip(&[("[Content_Types].xml", b"<Types/>"), ("word/document.xml", b"<w:document/>")]);
+        assert_eq!(sniff_content("upload.bin", &docx), ContentType::Docx);
+        let epub = test_zip(&[("mimetype", b"application/epub+zip")]);
+        assert_eq!(sniff_content("book.zip", &epub), ContentType::Epub);
+        let xlsx = test_zip(&[("[Content_Types].xml", b"<Types/>"), ("xl/workbook.xml", b"<workbook/>")]);
+        assert!(matches!(sniff_content("table.xlsx", &xlsx), ContentType::Unsupported(_)));
+        let plain = test_zip(&[("a.md", b"# A")]);
+        assert_eq!(sniff_content("docs.zip", &plain), ContentType::Archive(ArchiveKind::Zip));
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(b"plain log line\n").unwrap();
+        let gz = gz.finish().unwrap();
+        assert_eq!(sniff_content("app.log.gz", &gz), ContentType::Gzip);
+        assert_eq!(gunzip(&gz, 1024).unwrap(), b"plain log line\n");
+        assert!(gunzip(&gz, 4).is_err());
+    }
+
+    #[test]
+    fn test_text_starting_like_magic() {
+        for text in [
+            "ID3 tags are stored at the start of MP3 files.\n",
+            "BZh is how bzip2 streams begin.\n",
+            "RIFF is a container format.\n",
+            "OggS marks an Ogg page.\n",
+            "fLaC files start with a STREAMINFO block.\n",
+            "wOFF fonts wrap sfnt tables.\n",
+        ] {
+            assert_eq!(sniff_content("notes.md", text.as_bytes()), ContentType::Text, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_text_encodings() {
+        let utf16le: Vec<u8> = "Hello, world".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        assert_eq!(sniff_content("a.txt", &utf16le), ContentType::Text);
+        assert_eq!(decode_to_utf8_hard(&utf16le), "Hello, world");
+
+        let mut bom_be = vec![0xFE, 0xFF];
+        bom_be.extend("Привет".encode_utf16().flat_map(u16::to_be_bytes));
+        assert_eq!(sniff_content("a.txt", &bom_be), ContentType::Text);
+        assert_eq!(decode_to_utf8_hard(&bom_be), "Привет");
+
+        // BOM-less UTF-16 outside ASCII: Cyrillic code units have no zero bytes
+        for text in ["Привет", "Привет, мир! Как дела?"] {
+            let le: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+            assert_eq!(sniff_content("a.txt", &le), ContentType::Text);
+            assert_eq!(decode_to_utf8_hard(&le), text);
+            let be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+            assert_eq!(sniff_content("a.txt", &be), ContentType::Text);
+            assert_eq!(decode_to_utf8_hard(&be), text);
+        }
+        assert_eq!(text_encoding(b"======== plain ASCII banner ========"), UTF_8);
+
+        let (cp1251, _, _) = encoding_rs::WINDOWS_1251.encode("Съешь же ещё этих мягких французских булок");
+        assert_eq!(sniff_content("a.txt", &cp1251), ContentType::Text);
+        assert_eq!(decode_to_utf8_hard(&cp1251), "Съешь же ещё этих мягких французских булок");
+
+        // A sample cut in the middle of a multi-byte character is still UTF-8
+        let utf8 = "ёж".as_bytes();
+        assert_eq!(text_encoding(&utf8[..3]), UTF_8);
+    }
+}
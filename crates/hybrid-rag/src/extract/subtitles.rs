@@ -0,0 +1,232 @@
+// file: src/extract/subtitles.rs
+//
+// Субтитры и расшифровки встреч (.srt, .vtt): каждая реплика — абзац
+// `Спикер: текст`, чанкер сам склеивает реплики до размеров `ChunkingConfig`.
+// Время реплики — `Region` с полями `time_start`/`time_end` (секунды), так что
+// результат поиска указывает на момент в записи, а не на байты файла.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+use super::{extension, Extracted, Region};
+
+lazy_static! {
+    /// `<v Alice>` / `<v.loud Alice Smith>` — голос в WebVTT
+    static ref RE_VOICE: Regex = Regex::new(r"<v(?:\.[\w.-]+)?\s+([^>]+)>").unwrap();
+    /// Прочая разметка: `<i>`, `<c.yellow>`, `<00:00:01.000>`, `{\an8}` из SSA
+    static ref RE_TAG: Regex = Regex::new(r"<[^>]*>|\{\\[^}]*\}").unwrap();
+    /// "[Alice]", "- [Alice]" в начале реплики
+    static ref RE_SPEAKER_BRACKET: Regex = Regex::new(r"^(?:-\s*)?\[([^\]]{1,40})\]\s+").unwrap();
+    /// "Alice:", "- Mary Jane:" — 1–3 слова с заглавной буквы без знаков препинания
+    static ref RE_SPEAKER_COLON: Regex =
+        Regex::new(r"^(?:-\s*)?(\p{Lu}[\p{L}\p{N}'’-]*(?: \p{Lu}[\p{L}\p{N}'’-]*){0,2}):\s+").unwrap();
+}
+
+/// Субтитры ли это (по расширению).
+pub fn is_subtitle_format(name: &str) -> bool {
+    matches!(extension(name).as_str(), "srt" | "vtt")
+}
+
+/// Одна реплика: время в секундах, спикер (если указан) и текст без разметки.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start: f64,
+    pub end: f64,
+    pub speaker: Option<String>,
+    pub text: String,
+}
+
+/// Реплики SRT/WebVTT по порядку; блоки без таймкода (`WEBVTT`, `NOTE`,
+/// `STYLE`) пропускаются.
+///
+/// Метка `Имя:` считается спикером, только если метки есть хотя бы у половины
+/// реплик: иначе «Note: …» посреди обычных субтитров — часть текста.
+pub fn parse_cues(text: &str) -> Vec<Cue> {
+    let text = text.trim_start_matches('\u{FEFF}').replace("\r\n", "\n");
+    // (cue, label and body if the cue starts with "Name:")
+    let mut parsed: Vec<(Cue, Option<(String, String)>)> = Vec::new();
+    for block in text.split("\n\n") {
+        let mut lines = block.lines().skip_while(|l| !l.contains("-->"));
+        let Some((start, end)) = lines.next().and_then(parse_timing) else { continue };
+        let raw = lines.map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<_>>().join(" ");
+
+        let mut speaker = RE_VOICE.captures(&raw).map(|c| c[1].trim().to_string());
+        let mut body = RE_TAG.replace_all(&raw, "").split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut label = None;
+        if speaker.is_none() {
+            if let Some(c) = RE_SPEAKER_BRACKET.captures(&body) {
+                speaker = Some(c[1].trim().to_string());
+                body = body[c.get(0).unwrap().end()..].to_string();
+            } else if let Some(c) = RE_SPEAKER_COLON.captures(&body) {
+                label = Some((c[1].to_string(), body[c.get(0).unwrap().end()..].to_string()));
+            }
+        }
+        if body.is_empty() {
+            continue;
+        }
+        parsed.push((Cue { start, end, speaker, text: body }, label));
+    }
+
+    let labelled = parsed.iter().filter(|(c, l)| c.speaker.is_some() || l.is_some()).count();
+    let colon_speakers = parsed.len() >= 2 && labelled * 2 >= parsed.len();
+    let mut cues: Vec<Cue> = Vec::new();
+    for (mut cue, label) in parsed {
+        if let Some((speaker, body)) = label.filter(|_| colon_speakers) {
+            cue.speaker = Some(speaker);
+            cue.text = body;
+        }
+        // Auto-generated captions repeat the previous line while it scrolls
+        if let Some(prev) = cues.last_mut().filter(|p: &&mut Cue| p.text == cue.text && p.speaker == cue.speaker) {
+            prev.end = cue.end;
+            continue;
+        }
+        cues.push(cue);
+    }
+    cues
+}
+
+/// `00:01:02,500 --> 00:01:04.000 align:start` → (62.5, 64.0)
+fn parse_timing(line: &str) -> Option<(f64, f64)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start.trim())?, parse_timestamp(end)?))
+}
+
+/// `HH:MM:SS,mmm` (SRT) или `[HH:]MM:SS.mmm` (WebVTT) → секунды.
+fn parse_timestamp(s: &str) -> Option<f64> {
+    let parts: Vec<&str> = s.split(':').collect();
+    if !(2..=3).contains(&parts.len()) {
+        return None;
+    }
+    let seconds: f64 = parts.last()?.replace(',', ".").parse().ok()?;
+    let mut total = seconds;
+    for (i, p) in parts[..parts.len() - 1].iter().rev().enumerate() {
+        total += p.parse::<u32>().ok()? as f64 * 60f64.powi(i as i32 + 1);
+    }
+    Some(total)
+}
+
+/// Текст расшифровки с участками по репликам; `None` — таймкодов нет,
+/// файл индексируется как обычный текст.
+pub fn extract_subtitles(text: &str) -> Option<Extracted> {
+    let cues = parse_cues(text);
+    if cues.is_empty() {
+        return None;
+    }
+
+    let mut out = Extracted::default();
+    let mut speakers: Vec<String> = Vec::new();
+    for cue in cues {
+        if !out.text.is_empty() {
+            out.text.push_str("\n\n");
+        }
+        let start = out.text.len();
+        if let Some(speaker) = &cue.speaker {
+            out.text.push_str(speaker);
+            out.text.push_str(": ");
+            if !speakers.contains(speaker) {
+                speakers.push(speaker.clone());
+            }
+        }
+        out.text.push_str(&cue.text);
+        let mut region = Region {
+            start,
+            end: out.text.len(),
+            ..Default::default()
+        };
+        region.fields.insert("time_start".into(), Value::from(millis(cue.start)));
+        region.fields.insert("time_end".into(), Value::from(millis(cue.end)));
+        out.regions.push(region);
+    }
+
+    // WebVTT header metadata: "Language: ru"
+    let header = text.trim_start_matches('\u{FEFF}').lines().take_while(|l| !l.trim().is_empty());
+    if let Some(lang) = header.filter_map(|l| l.strip_prefix("Language:")).map(str::trim).next() {
+        out.meta.extra.insert("lang".into(), Value::from(lang));
+    }
+    if !speakers.is_empty() {
+        out.meta.extra.insert("speakers".into(), Value::from(speakers));
+    }
+    Some(out)
+}
+
+/// Секунды с точностью до миллисекунды (без хвостов двоичной дроби).
+fn millis(seconds: f64) -> f64 {
+    (seconds * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::region_fields;
+
+    #[test]
+    fn test_srt_and_vtt_cues() {
+        let srt = "1\r\n00:00:01,000 --> 00:00:03,500\r\nАлиса: Начнём с релиза.\r\n\r\n\
+                   2\r\n00:00:04,000 --> 00:00:06,000\r\n<i>[Боб]</i> Готово\r\nк пятнице.\r\n";
+        let cues = parse_cues(srt);
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].speaker.as_deref(), Some("Алиса"));
+        assert_eq!(cues[0].text, "Начнём с релиза.");
+        assert_eq!(cues[1].speaker.as_deref(), Some("Боб"));
+        assert_eq!(cues[1].text, "Готово к пятнице.");
+        assert_eq!((cues[1].start, cues[1].end), (4.0, 6.0));
+
+        let vtt = "WEBVTT\nLanguage: en\n\nNOTE exported by Meet\n\n\
+                   intro\n01:02.250 --> 01:04.000 align:start\n<v.loud Carol Smith>Hello <c.yellow>all</c></v>\n\n\
+                   01:04.000 --> 01:05.000\n<v Carol Smith>Hello all\n\n\
+                   1:00:00.000 --> 1:00:01.000\nNo speaker here\n";
+        let cues = parse_cues(vtt);
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0].speaker.as_deref(), Some("Carol Smith"));
+        assert_eq!(cues[0].text, "Hello all");
+        // The repeated caption extends the previous cue
+        assert_eq!((cues[0].start, cues[0].end), (62.25, 65.0));
+        assert_eq!(cues[1].start, 3600.0);
+        assert_eq!(cues[1].speaker, None);
+    }
+
+    #[test]
+    fn test_extract_subtitles_regions() {
+        let srt = "1\n00:00:01,000 --> 00:00:02,000\nAnn: One.\n\n\
+                   2\n00:00:02,000 --> 00:00:03,000\nBob: Two.\n\n\
+                   3\n00:00:03,000 --> 00:00:04,100\nAnn: Three.\n";
+        let ex = extract_subtitles(srt).unwrap();
+        assert_eq!(ex.text, "Ann: One.\n\nBob: Two.\n\nAnn: Three.");
+        assert_eq!(ex.meta.extra["speakers"], serde_json::json!(["Ann", "Bob"]));
+
+        // A chunk spanning the last two cues points at 2.0..4.1 s
+        let at = ex.text.find("Bob").unwrap();
+        let fields = region_fields(&ex.regions, at, ex.text.len());
+        assert_eq!(fields["time_start"], 2.0);
+        assert_eq!(fields["time_end"], 4.1);
+
+        assert!(extract_subtitles("just some text").is_none());
+        assert!(is_subtitle_format("meeting.VTT"));
+    }
+
+    #[test]
+    fn test_ordinary_speech_is_not_a_speaker() {
+        let srt = "1\n00:00:01,000 --> 00:00:02,000\nNote: bring the slides.\n\n\
+                   2\n00:00:02,000 --> 00:00:03,000\nThe plan is: ship on Friday.\n\n\
+                   3\n00:00:03,000 --> 00:00:04,000\nSo we agreed.\n";
+        let cues = parse_cues(srt);
+        assert_eq!(cues[0].speaker, None);
+        assert_eq!(cues[0].text, "Note: bring the slides.");
+        assert_eq!(cues[1].speaker, None);
+        assert_eq!(cues[1].text, "The plan is: ship on Friday.");
+        assert!(!extract_subtitles(srt).unwrap().meta.extra.contains_key("speakers"));
+
+        // Labels that are not 1–3 capitalised words stay text even in a transcript
+        let srt = "1\n00:00:01,000 --> 00:00:02,000\nMary Jane: The plan is: ship it.\n\n\
+                   2\n00:00:02,000 --> 00:00:03,000\nthe plan is: wait.\n\n\
+                   3\n00:00:03,000 --> 00:00:04,000\nBob: Fine.\n";
+        let cues = parse_cues(srt);
+        assert_eq!(cues[0].speaker.as_deref(), Some("Mary Jane"));
+        assert_eq!(cues[0].text, "The plan is: ship it.");
+        assert_eq!(cues[1].speaker, None);
+        assert_eq!(cues[1].text, "the plan is: wait.");
+        assert_eq!(cues[2].speaker.as_deref(), Some("Bob"));
+    }
+}
@@ -2,28 +2,76 @@
 //
 // Модуль для индексации документов в Qdrant
 
-use anyhow::Result;
+use anyhow::{bail, Result};
+use serde::Serialize;
 use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt};
 
 use qdrant_client::Qdrant;
 use qdrant_client::qdrant::{
     point_id::PointIdOptions, 
-    Condition, CreateCollectionBuilder, DeletePointsBuilder, Distance, Filter, PointId, PointStruct,
-    PointsIdsList, ScrollPointsBuilder, UpsertPointsBuilder, VectorParams, Vectors, VectorsConfig,
-    Value,
+    Condition, CreateCollectionBuilder, DeletePointsBuilder, Distance, Filter, PayloadIncludeSelector,
+    PointId, PointStruct, PointsIdsList, ScrollPointsBuilder, UpsertPointsBuilder, VectorParams,
+    Vectors, VectorsConfig, Value,
     vectors_config::Config as VectorsConfigOneOf,
 };
 
-use crate::chunking::{chunk_document, Chunk, ChunkingConfig};
+use crate::chunking::{
+    chunk_annotated, chunker_for, looks_annotated, parse_annotated, parse_front_matter, passage_text, Chunk,
+    Chunker, ChunkingConfig, ChunkingStrategy, DocumentMeta, EmbedFn, StreamingChunker,
+    TokenCounter,
+};
+use crate::config::{NotebookConfig, RecordMapping};
+use crate::extract::{
+    decode_to_utf8_hard, extract_docx, extract_epub, extract_notebook, extract_odt, extract_pdf,
+    extract_text, gunzip, is_mail_format, is_notebook, is_record_format, is_subtitle_format,
+    parse_mail, parse_records, region_fields, sniff_content, text_encoding, unpack_archive,
+    ArchiveKind, ArchiveLimits, ContentType, Extracted, Record, Region, UnsupportedContent,
+};
 use crate::onnx_embedder::ONNXEmbedder;
 
+/// Файлы больше этого размера индексируются потоково (`index_reader`)
+const STREAM_THRESHOLD_BYTES: u64 = 32 * 1024 * 1024;
+/// Сколько чанков эмбеддить и upsert'ить за раз при потоковой индексации
+pub const STREAM_BATCH_SIZE: usize = 64;
+
+/// Положение пачки чанков в документе (потоковая индексация пишет пачками).
+#[derive(Debug, Clone, Copy, Default)]
+struct ChunkLinks<'a> {
+    /// Порядковый номер первого чанка пачки
+    ordinal_base: usize,
+    /// Последний чанк предыдущей пачки
+    prev_id: Option<&'a str>,
+    /// Первый чанк следующей пачки
+    next_id: Option<&'a str>,
+}
+
+/// Итог индексации одного файла из архива.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ArchiveEntryResult {
+    /// Путь внутри архива
+    pub path: String,
+    /// `archive.zip!/path/inside`
+    pub source_id: String,
+    pub doc_ids: Vec<String>,
+    pub chunks: usize,
+    /// Почему файл не проиндексирован (пропущен или ошибка)
+    pub error: Option<String>,
+}
+
 /// Основной класс для управления индексацией
 pub struct DocumentIndexer {
     client: Qdrant,
-    embedder: ONNXEmbedder,
+    embedder: Arc<ONNXEmbedder>,
     collection: String,
     chunking_config: ChunkingConfig,
+    token_counter: TokenCounter,
+    chunker: Box<dyn Chunker>,
+    record_mapping: RecordMapping,
+    notebook: NotebookConfig,
+    archive_limits: ArchiveLimits,
 }
 
 impl DocumentIndexer {
@@ -36,16 +84,49 @@ impl DocumentIndexer {
         chunking_config: ChunkingConfig,
     ) -> Result<Self> {
         let client = Qdrant::from_url(qdrant_url).build()?;
-        let embedder = ONNXEmbedder::new(model_path, tokenizer_path)?;
+        let embedder = Arc::new(ONNXEmbedder::new(model_path, tokenizer_path)?);
+        // Считаем токены тем же токенайзером, что и модель
+        let token_counter =
+            TokenCounter::with_tokenizer(embedder.tokenizer(), chunking_config.approx_chars_per_token);
+        let embed: EmbedFn = {
+            let embedder = embedder.clone();
+            Arc::new(move |s: &str| embedder.embed_passage(s))
+        };
+        let chunker = chunker_for(&chunking_config, &token_counter, Some(embed))?;
 
         Ok(Self {
             client,
             embedder,
             collection,
             chunking_config,
+            token_counter,
+            chunker,
+            record_mapping: RecordMapping::default(),
+            notebook: NotebookConfig::default(),
+            archive_limits: ArchiveLimits::default(),
         })
     }
 
+    /// Заменить стратегию чанкинга (своя реализация `Chunker`)
+    pub fn set_chunker(&mut self, chunker: Box<dyn Chunker>) {
+        self.chunker = chunker;
+    }
+
+    /// Маппинг полей для CSV/JSON/JSONL (текст, метаданные, id записи)
+    pub fn set_record_mapping(&mut self, mapping: RecordMapping) {
+        self.record_mapping = mapping;
+    }
+
+    /// Настройки Jupyter-ноутбуков (выводы ячеек)
+    pub fn set_notebook_config(&mut self, notebook: NotebookConfig) {
+        self.notebook = notebook;
+    }
+
+    /// Лимиты распаковки архивов и .gz (число файлов, размеры)
+    pub fn set_archive_limits(&mut self, limits: ArchiveLimits) {
+        self.archive_limits = limits;
+    }
+
     /// Инициализировать коллекцию (создать если не существует)
     pub async fn ensure_collection(&self) -> Result<()> {
         let dim = self.embedder.embed_passage("probe")?.len();
@@ -79,16 +160,54 @@ impl DocumentIndexer {
         doc_id: &str,
         source_id: &str,
         text: &str,
+    ) -> Result<usize> {
+        self.index_document_with_meta(doc_id, source_id, text, DocumentMeta::default())
+            .await
+    }
+
+    /// Индексировать документ с известными метаданными (front matter дополняет их)
+    pub async fn index_document_with_meta(
+        &self,
+        doc_id: &str,
+        source_id: &str,
+        text: &str,
+        meta: DocumentMeta,
+    ) -> Result<usize> {
+        self.index_with_regions(doc_id, source_id, text, meta, &[])
+            .await
+    }
+
+    /// Индексировать результат экстрактора: поля участков (`page` и т.п.)
+    /// попадают в payload пересекающих их чанков.
+    pub async fn index_extracted(
+        &self,
+        doc_id: &str,
+        source_id: &str,
+        ex: Extracted,
+    ) -> Result<usize> {
+        self.index_with_regions(doc_id, source_id, &ex.text, ex.meta, &ex.regions)
+            .await
+    }
+
+    async fn index_with_regions(
+        &self,
+        doc_id: &str,
+        source_id: &str,
+        text: &str,
+        mut meta: DocumentMeta,
+        regions: &[Region],
     ) -> Result<usize> {
         // 1. Чанкинг
-        let chunks = chunk_document(doc_id, text, &self.chunking_config);
+        let chunks = self.chunk_text(doc_id, text, &mut meta)?;
         if chunks.is_empty() {
             eprintln!("⚠️  WARN: no chunks produced for {}", source_id);
             return Ok(0);
         }
 
         // 2. Создать points с эмбеддингами
-        let (points, keep_ids) = self.create_points(&chunks, doc_id, source_id).await?;
+        let (points, keep_ids) = self
+            .create_points(&chunks, doc_id, source_id, &meta, regions, ChunkLinks::default())
+            .await?;
 
         // 3. Upsert
         self.client
@@ -107,13 +226,270 @@ impl DocumentIndexer {
         Ok(keep_ids.len())
     }
 
-    /// Индексировать файл
+    /// Индексировать записи (строки CSV, письма mbox) как отдельные документы:
+    /// `source_id#<id>` (или номер записи).
+    /// `doc_id` — хэш этого адреса: с `id` документ стабилен при правке и
+    /// перестановке записей, без него привязан к номеру записи в файле.
+    /// Точки всех записей upsert'ятся пачками по `STREAM_BATCH_SIZE`; записи
+    /// прошлой версии файла, которых больше нет (удалённые или изменённые
+    /// без `id`), и устаревшие чанки оставшихся удаляются одним проходом.
+    /// Возвращает `(doc_id, чанков)` по каждой записи.
+    pub async fn index_records(
+        &self,
+        source_id: &str,
+        records: Vec<Record>,
+    ) -> Result<Vec<(String, usize)>> {
+        let previous = self.record_set_points(source_id).await?;
+        let mut indexed = Vec::with_capacity(records.len());
+        let mut keep_ids: Vec<String> = Vec::new();
+        let mut pending: Vec<PointStruct> = Vec::new();
+        let addresses = record_doc_ids(source_id, &records);
+        for ((doc_id, record_source), mut record) in addresses.into_iter().zip(records) {
+            record
+                .meta
+                .extra
+                .insert("record_set".into(), serde_json::Value::from(source_id));
+            let chunks = self.chunk_text(&doc_id, &record.text, &mut record.meta)?;
+            let (points, ids) = self
+                .create_points(&chunks, &doc_id, &record_source, &record.meta, &[], ChunkLinks::default())
+                .await?;
+            pending.extend(points);
+            if pending.len() >= STREAM_BATCH_SIZE {
+                self.client
+                    .upsert_points(UpsertPointsBuilder::new(&self.collection, std::mem::take(&mut pending)))
+                    .await?;
+            }
+            indexed.push((doc_id, ids.len()));
+            keep_ids.extend(ids);
+        }
+        if !pending.is_empty() {
+            self.client
+                .upsert_points(UpsertPointsBuilder::new(&self.collection, pending))
+                .await?;
+        }
+
+        let (stale_points, stale) =
+            stale_record_points(previous, indexed.iter().map(|(doc_id, _)| doc_id.as_str()), &keep_ids);
+        self.delete_points(stale_points).await?;
+        println!(
+            "📇 Indexed {} records from {} ({} stale removed)",
+            indexed.len(),
+            source_id,
+            stale.len()
+        );
+        Ok(indexed)
+    }
+
+    /// Индексировать поток: чанки читаются, эмбеддятся и upsert'ятся пачками
+    /// по `batch_size`, весь текст в память не загружается.
+    /// Всегда блочный чанкинг (`markdown-block`): остальным стратегиям нужен весь документ.
+    pub async fn index_reader<R: AsyncRead + Unpin>(
+        &self,
+        doc_id: &str,
+        source_id: &str,
+        reader: R,
+        mut meta: DocumentMeta,
+        batch_size: usize,
+    ) -> Result<usize> {
+        let mut chunker =
+            StreamingChunker::new(doc_id, reader, &self.chunking_config, &self.token_counter);
+        let mut keep_ids: Vec<String> = Vec::new();
+        let mut prev_id: Option<String> = None;
+        // First chunk of the next batch, read ahead for the `next_chunk_id` link
+        let mut lookahead: Option<Chunk> = None;
+
+        loop {
+            let batch_size = batch_size.max(1);
+            let mut batch: Vec<Chunk> = lookahead.take().into_iter().collect();
+            batch.extend(chunker.next_batch(batch_size - batch.len()).await?);
+            if batch.is_empty() {
+                break;
+            }
+            if keep_ids.is_empty() {
+                if let Some(fm) = chunker.front_matter() {
+                    meta.merge(fm.clone());
+                }
+            }
+            lookahead = chunker.next_chunk().await?;
+            let links = ChunkLinks {
+                ordinal_base: keep_ids.len(),
+                prev_id: prev_id.as_deref(),
+                next_id: lookahead.as_ref().map(|c| c.id.as_str()),
+            };
+            let (points, ids) = self
+                .create_points(&batch, doc_id, source_id, &meta, &[], links)
+                .await?;
+            self.client
+                .upsert_points(UpsertPointsBuilder::new(&self.collection, points))
+                .await?;
+            keep_ids.extend(ids);
+            prev_id = batch.last().map(|c| c.id.clone());
+        }
+
+        if keep_ids.is_empty() {
+            eprintln!("⚠️  WARN: no chunks produced for {}", source_id);
+            return Ok(0);
+        }
+        self.delete_stale_chunks(doc_id, &keep_ids).await?;
+
+        println!(
+            "✅ Indexed (stream): doc_id={}, chunks={}",
+            &doc_id[..24.min(doc_id.len())],
+            keep_ids.len()
+        );
+
+        Ok(keep_ids.len())
+    }
+
+    /// Индексировать файл (большие файлы — потоково)
     pub async fn index_file(&self, path: &std::path::Path, source_id: &str) -> Result<usize> {
+        let size = tokio::fs::metadata(path).await?.len();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let as_records = is_record_format(name) && self.record_mapping.is_enabled();
+        if size > STREAM_THRESHOLD_BYTES
+            && self.chunker.name() == ChunkingStrategy::Blocks.as_str()
+            && !as_records
+            && !is_mail_format(name)
+            && !is_subtitle_format(name)
+            && !is_notebook(name)
+        {
+            // Only plain UTF-8 text streams; HTML, subtitles and annotated
+            // files need the whole text for their extractor or parser
+            let mut head = Vec::new();
+            tokio::fs::File::open(path)
+                .await?
+                .take(8192)
+                .read_to_end(&mut head)
+                .await?;
+            let head_text = String::from_utf8_lossy(&head);
+            if sniff_content(name, &head) == ContentType::Text
+                && text_encoding(&head) == encoding_rs::UTF_8
+                && extract_text(name, &head_text).is_none()
+                && !looks_annotated(&head_text)
+            {
+                let doc_id = compute_doc_id_file(path).await?;
+                let file = tokio::io::BufReader::new(tokio::fs::File::open(path).await?);
+                return self
+                    .index_reader(&doc_id, source_id, file, DocumentMeta::default(), STREAM_BATCH_SIZE)
+                    .await;
+            }
+        }
+
         let raw = tokio::fs::read(path).await?;
-        let text = String::from_utf8_lossy(&raw).to_string();
-        let doc_id = compute_doc_id(&raw);
+        if let ContentType::Archive(kind) = sniff_content(name, &raw) {
+            let results = self
+                .index_archive(source_id, kind, &raw, &self.archive_limits)
+                .await?;
+            return Ok(results.iter().map(|r| r.chunks).sum());
+        }
+        let indexed = self.index_bytes(name, &compute_doc_id(&raw), source_id, &raw).await?;
+        Ok(indexed.iter().map(|(_, n)| n).sum())
+    }
+
+    /// Индексировать содержимое файла: тип определяется по сигнатуре
+    /// (`sniff_content`), бинарные форматы идут через экстракторы, почта и
+    /// CSV/JSON — по записям, остальной текст декодируется в UTF-8.
+    /// Неподдерживаемый тип — ошибка `UnsupportedContent`.
+    /// Возвращает `(doc_id, чанков)` по каждому документу.
+    pub async fn index_bytes(
+        &self,
+        name: &str,
+        doc_id: &str,
+        source_id: &str,
+        raw: &[u8],
+    ) -> Result<Vec<(String, usize)>> {
+        let mut content = sniff_content(name, raw);
+        let mut name = name;
+        let unpacked;
+        let mut raw = raw;
+        if content == ContentType::Gzip {
+            // A single compressed file: index what is inside
+            unpacked = gunzip(raw, self.archive_limits.max_entry_bytes)?;
+            raw = &unpacked;
+            if name.to_ascii_lowercase().ends_with(".gz") {
+                name = &name[..name.len() - 3];
+            }
+            content = sniff_content(name, raw);
+        }
 
-        self.index_document(&doc_id, source_id, &text).await
+        let ex = match content {
+            ContentType::Pdf => extract_pdf(raw)?,
+            ContentType::Docx => extract_docx(raw)?,
+            ContentType::Odt => extract_odt(raw)?,
+            ContentType::Epub => extract_epub(raw)?,
+            ContentType::Archive(_) | ContentType::Gzip => {
+                bail!("nested archives are not unpacked: {}", name)
+            }
+            ContentType::Unsupported(what) => {
+                return Err(UnsupportedContent {
+                    name: name.to_string(),
+                    what,
+                }
+                .into())
+            }
+            ContentType::Text => {
+                // mail-parser decodes MIME charsets itself, so it gets raw bytes
+                if let Some(mails) = parse_mail(name, raw)? {
+                    return self.index_records(source_id, mails).await;
+                }
+                let text = decode_to_utf8_hard(raw);
+                if let Some(records) = parse_records(name, &text, &self.record_mapping)? {
+                    return self.index_records(source_id, records).await;
+                }
+                if is_notebook(name) {
+                    extract_notebook(&text, &self.notebook)?
+                } else if let Some(ex) = extract_text(name, &text) {
+                    ex
+                } else {
+                    let chunks = self.index_document(doc_id, source_id, &text).await?;
+                    return Ok(vec![(doc_id.to_string(), chunks)]);
+                }
+            }
+        };
+        let chunks = self.index_extracted(doc_id, source_id, ex).await?;
+        Ok(vec![(doc_id.to_string(), chunks)])
+    }
+
+    /// Индексировать каждый файл архива отдельным документом с `source_id`
+    /// вида `archive.zip!/path/inside`. Ошибка одного файла не прерывает
+    /// остальные — она попадает в его `ArchiveEntryResult`; вложенные архивы
+    /// не распаковываются.
+    pub async fn index_archive(
+        &self,
+        source_id: &str,
+        kind: ArchiveKind,
+        raw: &[u8],
+        limits: &ArchiveLimits,
+    ) -> Result<Vec<ArchiveEntryResult>> {
+        let entries = unpack_archive(kind, raw, limits)?;
+        let mut results = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let mut result = ArchiveEntryResult {
+                source_id: format!("{}!/{}", source_id, entry.path),
+                ..Default::default()
+            };
+            if let Some(reason) = entry.skipped {
+                result.error = Some(reason);
+            } else {
+                let doc_id = compute_doc_id(&entry.bytes);
+                match self.index_bytes(&entry.path, &doc_id, &result.source_id, &entry.bytes).await {
+                    Ok(indexed) => {
+                        result.chunks = indexed.iter().map(|(_, n)| n).sum();
+                        result.doc_ids = indexed.into_iter().map(|(id, _)| id).collect();
+                    }
+                    Err(e) => {
+                        eprintln!("❌ Error indexing {}: {:#}", result.source_id, e);
+                        result.error = Some(format!("{:#}", e));
+                    }
+                }
+            }
+            result.path = entry.path;
+            results.push(result);
+        }
+
+        let ok = results.iter().filter(|r| r.error.is_none()).count();
+        println!("🗜️  Indexed {}/{} files from {}", ok, results.len(), source_id);
+        Ok(results)
     }
 
     /// Индексировать директорию
@@ -164,12 +540,17 @@ impl DocumentIndexer {
         chunks: &[Chunk],
         doc_id: &str,
         source_id: &str,
+        meta: &DocumentMeta,
+        regions: &[Region],
+        links: ChunkLinks<'_>,
     ) -> Result<(Vec<PointStruct>, Vec<String>)> {
         let mut points = Vec::with_capacity(chunks.len());
         let mut keep_ids = Vec::with_capacity(chunks.len());
 
-        for chunk in chunks {
-            let embedding = self.embedder.embed_passage(&chunk.text)?;
+        for (i, chunk) in chunks.iter().enumerate() {
+            let embedding = self
+                .embedder
+                .embed_passage(&passage_text(chunk, &self.chunking_config))?;
             let numeric_id = chunk_id_to_u64(&chunk.id);
 
             let mut payload: HashMap<String, Value> = HashMap::new();
@@ -183,6 +564,37 @@ impl DocumentIndexer {
                     Value::from(chunk.end as i64),
                 ]),
             );
+            // Порядок в документе и соседи (для расширения хита контекстом)
+            payload.insert("ordinal".into(), Value::from((links.ordinal_base + i) as i64));
+            let prev = if i > 0 { Some(chunks[i - 1].id.as_str()) } else { links.prev_id };
+            let next = chunks.get(i + 1).map(|c| c.id.as_str()).or(links.next_id);
+            if let Some(prev) = prev {
+                payload.insert("prev_chunk_id".into(), Value::from(prev.to_string()));
+            }
+            if let Some(next) = next {
+                payload.insert("next_chunk_id".into(), Value::from(next.to_string()));
+            }
+            payload.insert(
+                "line_span".into(),
+                Value::from(vec![
+                    Value::from(chunk.pos.start_line as i64),
+                    Value::from(chunk.pos.end_line as i64),
+                ]),
+            );
+            payload.insert(
+                "col_span".into(),
+                Value::from(vec![
+                    Value::from(chunk.pos.start_col as i64),
+                    Value::from(chunk.pos.end_col as i64),
+                ]),
+            );
+            payload.insert(
+                "char_span".into(),
+                Value::from(vec![
+                    Value::from(chunk.pos.char_start as i64),
+                    Value::from(chunk.pos.char_end as i64),
+                ]),
+            );
             payload.insert(
                 "kinds".into(),
                 Value::from(
@@ -193,6 +605,24 @@ impl DocumentIndexer {
                         .collect::<Vec<_>>(),
                 ),
             );
+            payload.insert(
+                "headings".into(),
+                Value::from(
+                    chunk
+                        .headings
+                        .iter()
+                        .map(|h| Value::from(h.clone()))
+                        .collect::<Vec<_>>(),
+                ),
+            );
+            if let Some(lang) = &chunk.lang {
+                payload.insert("lang".into(), Value::from(lang.clone()));
+            }
+            insert_meta(&mut payload, meta);
+            // Положение в исходном формате (страница PDF и т.п.)
+            for (key, value) in region_fields(regions, chunk.start, chunk.end) {
+                payload.insert(key, Value::from(value));
+            }
             payload.insert("text_len".into(), Value::from(chunk.text.len() as i64));
             payload.insert("text".into(), Value::from(chunk.text.clone()));
 
@@ -211,6 +641,56 @@ impl DocumentIndexer {
         Ok((points, keep_ids))
     }
 
+    /// Чанки (обычный, размеченный `<<<BLOCK:n>>>` текст — уже сегментирован
+    /// вручную); front matter дополняет `meta`.
+    fn chunk_text(&self, doc_id: &str, text: &str, meta: &mut DocumentMeta) -> Result<Vec<Chunk>> {
+        if let Some((_, fm)) = parse_front_matter(text) {
+            meta.merge(fm);
+        }
+        match parse_annotated(text) {
+            Some(doc) => Ok(chunk_annotated(doc_id, text, &doc, &self.chunking_config, &self.token_counter)),
+            None => self.chunker.chunk(doc_id, text),
+        }
+    }
+
+    /// Точки `(id, doc_id)` записей, проиндексированных из `source_id` раньше (`meta.record_set`).
+    async fn record_set_points(&self, source_id: &str) -> Result<Vec<(String, String)>> {
+        let filter = Filter {
+            must: vec![Condition::matches("meta.record_set", source_id.to_string())],
+            ..Default::default()
+        };
+        let mut points = Vec::new();
+        let mut next_offset: Option<PointId> = None;
+        loop {
+            let mut builder = ScrollPointsBuilder::new(&self.collection)
+                .filter(filter.clone())
+                .limit(1000)
+                .with_payload(PayloadIncludeSelector {
+                    fields: vec!["doc_id".into()],
+                })
+                .with_vectors(false);
+            if let Some(offset) = next_offset.clone() {
+                builder = builder.offset(offset);
+            }
+
+            let page = self.client.scroll(builder).await?;
+            for rec in page.result {
+                let id = rec.id.and_then(|pid| point_id_string(&pid));
+                if let (Some(id), Some(qdrant_client::qdrant::value::Kind::StringValue(doc_id))) =
+                    (id, rec.payload.get("doc_id").and_then(|v| v.kind.clone()))
+                {
+                    points.push((id, doc_id));
+                }
+            }
+
+            if page.next_page_offset.is_none() {
+                break;
+            }
+            next_offset = page.next_page_offset.clone();
+        }
+        Ok(points)
+    }
+
     async fn delete_stale_chunks(&self, doc_id: &str, keep_ids: &[String]) -> Result<()> {
         let filter = Filter {
             must: vec![Condition::matches("doc_id", doc_id.to_string())],
@@ -233,15 +713,7 @@ impl DocumentIndexer {
 
             let page = self.client.scroll(builder).await?;
 
-            for rec in page.result {
-                if let Some(pid) = rec.id {
-                    match pid.point_id_options {
-                        Some(PointIdOptions::Num(n)) => existing_ids.push(n.to_string()),
-                        Some(PointIdOptions::Uuid(u)) => existing_ids.push(u),
-                        _ => {}
-                    }
-                }
-            }
+            existing_ids.extend(page.result.iter().filter_map(|rec| rec.id.as_ref().and_then(point_id_string)));
 
             if page.next_page_offset.is_none() {
                 break;
@@ -249,11 +721,13 @@ impl DocumentIndexer {
             next_offset = page.next_page_offset.clone();
         }
 
-        // Найти ID для удаления
-        let keep: HashSet<&String> = keep_ids.iter().collect();
-        let to_delete: Vec<PointId> = existing_ids
+        self.delete_points(points_except(existing_ids, keep_ids)).await
+    }
+
+    /// Удалить точки по id.
+    async fn delete_points(&self, ids: Vec<String>) -> Result<()> {
+        let to_delete: Vec<PointId> = ids
             .into_iter()
-            .filter(|id| !keep.contains(id))
             .filter_map(|id| {
                 id.parse::<u64>().ok().map(|num| PointId {
                     point_id_options: Some(PointIdOptions::Num(num)),
@@ -276,12 +750,117 @@ impl DocumentIndexer {
 
 // === Helper functions ===
 
+/// Метаданные документа: title/author/tags на верхнем уровне (для фильтров), остальное в `meta`.
+fn insert_meta(payload: &mut HashMap<String, Value>, meta: &DocumentMeta) {
+    if let Some(title) = &meta.title {
+        payload.insert("title".into(), Value::from(title.clone()));
+    }
+    if let Some(author) = &meta.author {
+        payload.insert("author".into(), Value::from(author.clone()));
+    }
+    if !meta.tags.is_empty() {
+        payload.insert(
+            "tags".into(),
+            Value::from(
+                meta.tags
+                    .iter()
+                    .map(|t| Value::from(t.clone()))
+                    .collect::<Vec<_>>(),
+            ),
+        );
+    }
+    if !meta.extra.is_empty() {
+        let extra: serde_json::Map<String, serde_json::Value> = meta
+            .extra
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        payload.insert("meta".into(), Value::from(serde_json::Value::Object(extra)));
+    }
+}
+
+/// Id точки строкой (как в `keep_ids`).
+fn point_id_string(pid: &PointId) -> Option<String> {
+    match &pid.point_id_options {
+        Some(PointIdOptions::Num(n)) => Some(n.to_string()),
+        Some(PointIdOptions::Uuid(u)) => Some(u.clone()),
+        None => None,
+    }
+}
+
+/// `(doc_id, source_id)` каждой записи из файла `source_id`. Одинаковые
+/// записи в одном или разных файлах — разные документы. Повторный `id`
+/// (дубль SKU) получает суффикс с номером записи, иначе вторая запись
+/// перезаписала бы чанки первой.
+fn record_doc_ids(source_id: &str, records: &[Record]) -> Vec<(String, String)> {
+    let mut seen: HashSet<&str> = HashSet::new();
+    records
+        .iter()
+        .enumerate()
+        .map(|(i, record)| {
+            let key = match record.id.as_deref() {
+                Some(id) if seen.insert(id) => id.to_string(),
+                Some(id) => {
+                    eprintln!("⚠️  WARN: duplicate record id {} in {} (record {})", id, source_id, i);
+                    format!("{}@{}", id, i)
+                }
+                None => i.to_string(),
+            };
+            let record_source = format!("{}#{}", source_id, key);
+            (compute_doc_id(record_source.as_bytes()), record_source)
+        })
+        .collect()
+}
+
+/// Id из `existing_ids`, которых нет в `keep_ids`.
+fn points_except(existing_ids: Vec<String>, keep_ids: &[String]) -> Vec<String> {
+    let keep: HashSet<&String> = keep_ids.iter().collect();
+    existing_ids.into_iter().filter(|id| !keep.contains(id)).collect()
+}
+
+/// Что удалить после переиндексации набора записей: точки `(id, doc_id)`
+/// прошлой версии, которых нет среди `keep_ids` (удалённые записи и устаревшие
+/// чанки изменённых), и документы прошлой версии, которых нет в `current`.
+fn stale_record_points<'a>(
+    previous: Vec<(String, String)>,
+    current: impl IntoIterator<Item = &'a str>,
+    keep_ids: &[String],
+) -> (Vec<String>, Vec<String>) {
+    let current: HashSet<&str> = current.into_iter().collect();
+    let mut stale_docs: Vec<String> = previous
+        .iter()
+        .map(|(_, doc_id)| doc_id)
+        .filter(|doc_id| !current.contains(doc_id.as_str()))
+        .cloned()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    stale_docs.sort();
+    let points = points_except(previous.into_iter().map(|(id, _)| id).collect(), keep_ids);
+    (points, stale_docs)
+}
+
 pub fn compute_doc_id(content: &[u8]) -> String {
     let mut hasher = Sha256::new();
     hasher.update(content);
     format!("doc::{:x}", hasher.finalize())
 }
 
+/// `compute_doc_id` по файлу без загрузки его в память.
+pub async fn compute_doc_id_file(path: &std::path::Path) -> Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("doc::{:x}", hasher.finalize()))
+}
+
 fn chunk_id_to_u64(chunk_id: &str) -> u64 {
     if let Some(hex_part) = chunk_id.strip_prefix("chunk::") {
         u64::from_str_radix(&hex_part[..16.min(hex_part.len())], 16).unwrap_or_else(|_| {
@@ -299,4 +878,100 @@ fn hash_string_to_u64(s: &str) -> u64 {
     u64::from_be_bytes([
         bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7],
     ])
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::parse_records;
+
+    fn doc_ids(source_id: &str, csv: &str, mapping: &RecordMapping) -> Vec<String> {
+        points(source_id, csv, mapping).into_iter().map(|(_, doc_id)| doc_id).collect()
+    }
+
+    /// `(id точки, doc_id)` по одной точке на запись; id зависит от текста, как у чанков.
+    fn points(source_id: &str, csv: &str, mapping: &RecordMapping) -> Vec<(String, String)> {
+        let records = parse_records("items.csv", csv, mapping).unwrap().unwrap();
+        record_doc_ids(source_id, &records)
+            .into_iter()
+            .zip(&records)
+            .map(|((doc_id, _), r)| (chunk_id_to_u64(&format!("{}{}", doc_id, r.text)).to_string(), doc_id))
+            .collect()
+    }
+
+    fn ids(points: &[(String, String)]) -> Vec<String> {
+        points.iter().map(|(id, _)| id.clone()).collect()
+    }
+
+    #[test]
+    fn test_reingest_drops_removed_and_edited_records() {
+        let mapping = RecordMapping {
+            enabled: true,
+            ..Default::default()
+        };
+        let v1 = points("items.csv", "name,note\nA,first\nB,second\nC,third\n", &mapping);
+        // B is removed, C is edited: C is re-indexed under B's position, the last one goes
+        let v2 = points("items.csv", "name,note\nA,first\nC,third (fixed)\n", &mapping);
+
+        let (delete, stale) =
+            stale_record_points(v1.clone(), v2.iter().map(|(_, d)| d.as_str()), &ids(&v2));
+        // B's old chunk goes with the last record; A's chunk is kept
+        assert_eq!(delete, vec![v1[1].0.clone(), v1[2].0.clone()]);
+        assert_eq!(stale, vec![v1[2].1.clone()]);
+        assert_eq!(v2[0], v1[0]);
+        assert_eq!(v2[1].1, v1[1].1);
+    }
+
+    #[test]
+    fn test_identical_records_stay_separate() {
+        let mapping = RecordMapping {
+            enabled: true,
+            ..Default::default()
+        };
+        // Two identical rows in one file are two documents
+        let ids = doc_ids("log.csv", "msg\nretry\nretry\n", &mapping);
+        assert_ne!(ids[0], ids[1]);
+
+        // The same row in two files: re-ingesting one never touches the other
+        let a = doc_ids("a.csv", "msg\nretry\n", &mapping);
+        let b = doc_ids("b.csv", "msg\nretry\n", &mapping);
+        assert_ne!(a, b);
+        assert!(!a.contains(&b[0]));
+    }
+
+    #[test]
+    fn test_reingest_with_record_ids() {
+        let mapping = RecordMapping {
+            id_field: Some("sku".into()),
+            ..Default::default()
+        };
+        let v1 = points("goods.csv", "sku,title\nA-1,Кофемолка\nA-2,Чайник\n", &mapping);
+        let v2 = points("goods.csv", "sku,title\nA-2,Чайник электрический\n", &mapping);
+
+        // An edited record keeps its doc_id and is re-indexed in place
+        assert_eq!(v2[0].1, v1[1].1);
+        let (delete, stale) =
+            stale_record_points(v1.clone(), v2.iter().map(|(_, d)| d.as_str()), &ids(&v2));
+        assert_eq!(delete, ids(&v1));
+        assert_eq!(stale, vec![v1[0].1.clone()]);
+
+        // Re-ingesting the same file deletes nothing
+        let (delete, stale) = stale_record_points(v2.clone(), v2.iter().map(|(_, d)| d.as_str()), &ids(&v2));
+        assert!(delete.is_empty() && stale.is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_record_ids_stay_separate() {
+        let mapping = RecordMapping {
+            id_field: Some("sku".into()),
+            ..Default::default()
+        };
+        let csv = "sku,title\nA-1,Кофемолка\nA-1,Кофемолка ручная\nA-2,Чайник\n";
+        let records = parse_records("goods.csv", csv, &mapping).unwrap().unwrap();
+        let ids = record_doc_ids("goods.csv", &records);
+        assert_eq!(ids[0].1, "goods.csv#A-1");
+        assert_eq!(ids[1].1, "goods.csv#A-1@1");
+        assert_eq!(ids[2].1, "goods.csv#A-2");
+        assert_ne!(ids[0].0, ids[1].0);
+    }
+}
@@ -4,12 +4,14 @@
 //
 // Основные модули:
 // - chunking: Умный чанкинг документов
+// - extract: Извлечение текста из HTML и других форматов
 // - onnx_embedder: Эмбеддинги через ONNX Runtime
 // - ingest: Индексация документов в Qdrant
 // - query: Поиск и retrieval из Qdrant
 // - config: Конфигурация системы
 
 pub mod chunking;
+pub mod extract;
 pub mod onnx_embedder;
 pub mod ingest;
 pub mod query;
@@ -1,8 +1,8 @@
 // file: src/main.rs
-mod onnx_embedder;
-
 use anyhow::Result;
 use clap::Parser;
+use hybrid_rag::chunking::TokenCounter;
+use hybrid_rag::onnx_embedder::ONNXEmbedder;
 
 #[derive(Parser, Debug)]
 #[command(name = "embed")]
@@ -25,7 +25,10 @@ fn main() -> Result<()> {
     let args = Args::parse();
     let model_path = format!("{}/model.onnx", args.model_dir);
 
-    let embedder = onnx_embedder::ONNXEmbedder::new(&model_path, &args.tokenizer_path)?;
+    let embedder = ONNXEmbedder::new(&model_path, &args.tokenizer_path)?;
+    // Тот же счётчик, что и у чанкинга в `DocumentIndexer`
+    let tokens = TokenCounter::with_tokenizer(embedder.tokenizer(), 4.0).count(&args.text);
+    println!("🔤 Токенов в тексте: {}", tokens);
     let emb = embedder.embed(&args.text)?;
     println!("✅ Вектор готов. Длина: {}", emb.len());
     println!("Первые 8 значений: {:?}", &emb[..emb.len().min(8)]);
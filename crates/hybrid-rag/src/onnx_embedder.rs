@@ -131,6 +131,11 @@ impl ONNXEmbedder {
         Ok(pooled)
     }
 
+    /// Токенайзер модели (нужен чанкингу для точного подсчёта токенов).
+    pub fn tokenizer(&self) -> &Tokenizer {
+        &self.tokenizer
+    }
+
     /// E5-режим: эмбеддинг запроса (добавляет префикс `query:`).
     pub fn embed_query(&self, text: &str) -> Result<Vec<f32>> {
         self.embed(&format!("query: {}", text))
@@ -6,7 +6,7 @@ use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use qdrant_client::qdrant::{Condition, Filter, SearchPointsBuilder};
+use qdrant_client::qdrant::{Condition, Filter, Range, ScrollPointsBuilder, SearchPointsBuilder};
 use qdrant_client::Qdrant;
 
 use crate::onnx_embedder::ONNXEmbedder;
@@ -21,7 +21,32 @@ pub struct SearchResult {
     pub chunk_id: String,
     pub text: String,
     pub span: (usize, usize),
+    /// Строки начала и конца (с 1)
+    #[serde(default)]
+    pub line_span: Option<(usize, usize)>,
+    /// Колонки начала и конца в символах (с 1)
+    #[serde(default)]
+    pub col_span: Option<(usize, usize)>,
+    /// Смещения в Unicode-символах
+    #[serde(default)]
+    pub char_span: Option<(usize, usize)>,
     pub kinds: Vec<String>,
+    /// Порядковый номер чанка в документе
+    #[serde(default)]
+    pub ordinal: Option<usize>,
+    #[serde(default)]
+    pub prev_chunk_id: Option<String>,
+    #[serde(default)]
+    pub next_chunk_id: Option<String>,
+    #[serde(default)]
+    pub headings: Vec<String>,
+    #[serde(default)]
+    pub lang: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Начало и конец в записи, секунды (субтитры и расшифровки)
+    #[serde(default)]
+    pub time_span: Option<(f64, f64)>,
 }
 
 /// Класс для поиска документов
@@ -104,6 +129,20 @@ impl DocumentRetriever {
         Ok(results)
     }
 
+    /// Поиск по нескольким документам (например, записям одного CSV)
+    pub async fn search_in_documents(
+        &self,
+        query: &str,
+        doc_ids: &[String],
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let filter = Filter {
+            must: vec![Condition::matches("doc_id", doc_ids.to_vec())],
+            ..Default::default()
+        };
+        self.search_with_filter(query, filter, limit).await
+    }
+
     /// Поиск с произвольным фильтром
     pub async fn search_with_filter(
         &self,
@@ -210,42 +249,133 @@ impl DocumentRetriever {
         Ok(context)
     }
 
+    /// Хит вместе с `n` соседними чанками с каждой стороны (тот же `doc_id`),
+    /// в порядке документа. У соседей `score` = 0. Точки без `ordinal`
+    /// (проиндексированные до появления ссылок) возвращаются без соседей.
+    pub async fn expand_with_neighbors(
+        &self,
+        hit: &SearchResult,
+        n: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let Some(ordinal) = hit.ordinal else {
+            return Ok(vec![hit.clone()]);
+        };
+        if n == 0 {
+            return Ok(vec![hit.clone()]);
+        }
+
+        let page = self
+            .client
+            .scroll(
+                ScrollPointsBuilder::new(&self.collection)
+                    .filter(neighbor_filter(&hit.doc_id, ordinal, n))
+                    .limit((2 * n + 1) as u32)
+                    .with_payload(true)
+                    .with_vectors(false),
+            )
+            .await?;
+
+        let neighbors = page
+            .result
+            .into_iter()
+            .filter_map(|p| parse_point(p.id?, 0.0, p.payload))
+            .collect();
+        Ok(merge_neighbors(hit, neighbors))
+    }
+
     // === Private methods ===
 
     fn parse_search_result(
         &self,
         point: qdrant_client::qdrant::ScoredPoint,
     ) -> Option<SearchResult> {
-        let id = match point.id?.point_id_options? {
-            qdrant_client::qdrant::point_id::PointIdOptions::Num(n) => n.to_string(),
-            qdrant_client::qdrant::point_id::PointIdOptions::Uuid(u) => u,
-        };
-
-        let payload = point.payload;
-
-        let doc_id = extract_string(&payload, "doc_id")?;
-        let source_id = extract_string(&payload, "source_id")?;
-        let chunk_id = extract_string(&payload, "chunk_id")?;
-        let text = extract_string(&payload, "text")?;
-
-        let span = extract_span(&payload)?;
-        let kinds = extract_string_array(&payload, "kinds");
-
-        Some(SearchResult {
-            id,
-            score: point.score,
-            doc_id,
-            source_id,
-            chunk_id,
-            text,
-            span,
-            kinds,
-        })
+        parse_point(point.id?, point.score, point.payload)
     }
 }
 
 // === Helper functions ===
 
+/// Чанки документа `doc_id` с `ordinal` в пределах `n` от хита (не ниже 0).
+fn neighbor_filter(doc_id: &str, ordinal: usize, n: usize) -> Filter {
+    Filter {
+        must: vec![
+            Condition::matches("doc_id", doc_id.to_string()),
+            Condition::range(
+                "ordinal",
+                Range {
+                    gte: Some(ordinal.saturating_sub(n) as f64),
+                    lte: Some((ordinal + n) as f64),
+                    ..Default::default()
+                },
+            ),
+        ],
+        ..Default::default()
+    }
+}
+
+/// Соседи в порядке документа; сам хит — со своим `score`, даже если его нет в выдаче.
+fn merge_neighbors(hit: &SearchResult, neighbors: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut results: Vec<SearchResult> = neighbors
+        .into_iter()
+        .map(|r| if r.chunk_id == hit.chunk_id { hit.clone() } else { r })
+        .collect();
+    if !results.iter().any(|r| r.chunk_id == hit.chunk_id) {
+        results.push(hit.clone());
+    }
+    results.sort_by_key(|r| r.ordinal);
+    results
+}
+
+fn parse_point(
+    point_id: qdrant_client::qdrant::PointId,
+    score: f32,
+    payload: HashMap<String, qdrant_client::qdrant::Value>,
+) -> Option<SearchResult> {
+    let id = match point_id.point_id_options? {
+        qdrant_client::qdrant::point_id::PointIdOptions::Num(n) => n.to_string(),
+        qdrant_client::qdrant::point_id::PointIdOptions::Uuid(u) => u,
+    };
+
+    let doc_id = extract_string(&payload, "doc_id")?;
+    let source_id = extract_string(&payload, "source_id")?;
+    let chunk_id = extract_string(&payload, "chunk_id")?;
+    let text = extract_string(&payload, "text")?;
+
+    let span = extract_pair(&payload, "span")?;
+    let line_span = extract_pair(&payload, "line_span");
+    let col_span = extract_pair(&payload, "col_span");
+    let char_span = extract_pair(&payload, "char_span");
+    let kinds = extract_string_array(&payload, "kinds");
+    let ordinal = extract_usize(&payload, "ordinal");
+    let prev_chunk_id = extract_string(&payload, "prev_chunk_id");
+    let next_chunk_id = extract_string(&payload, "next_chunk_id");
+    let headings = extract_string_array(&payload, "headings");
+    let lang = extract_string(&payload, "lang");
+    let title = extract_string(&payload, "title");
+    let time_span = extract_f64(&payload, "time_start").zip(extract_f64(&payload, "time_end"));
+
+    Some(SearchResult {
+        id,
+        score,
+        doc_id,
+        source_id,
+        chunk_id,
+        text,
+        span,
+        line_span,
+        col_span,
+        char_span,
+        kinds,
+        ordinal,
+        prev_chunk_id,
+        next_chunk_id,
+        headings,
+        lang,
+        title,
+        time_span,
+    })
+}
+
 fn extract_string(
     payload: &HashMap<String, qdrant_client::qdrant::Value>,
     key: &str,
@@ -256,8 +386,11 @@ fn extract_string(
     })
 }
 
-fn extract_span(payload: &HashMap<String, qdrant_client::qdrant::Value>) -> Option<(usize, usize)> {
-    let span_value = payload.get("span")?;
+fn extract_pair(
+    payload: &HashMap<String, qdrant_client::qdrant::Value>,
+    key: &str,
+) -> Option<(usize, usize)> {
+    let span_value = payload.get(key)?;
 
     match span_value.kind.as_ref()? {
         qdrant_client::qdrant::value::Kind::ListValue(list) => {
@@ -279,6 +412,27 @@ fn extract_span(payload: &HashMap<String, qdrant_client::qdrant::Value>) -> Opti
     }
 }
 
+fn extract_usize(
+    payload: &HashMap<String, qdrant_client::qdrant::Value>,
+    key: &str,
+) -> Option<usize> {
+    payload.get(key).and_then(|v| match v.kind.as_ref()? {
+        qdrant_client::qdrant::value::Kind::IntegerValue(n) => usize::try_from(*n).ok(),
+        _ => None,
+    })
+}
+
+fn extract_f64(
+    payload: &HashMap<String, qdrant_client::qdrant::Value>,
+    key: &str,
+) -> Option<f64> {
+    payload.get(key).and_then(|v| match v.kind.as_ref()? {
+        qdrant_client::qdrant::value::Kind::DoubleValue(n) => Some(*n),
+        qdrant_client::qdrant::value::Kind::IntegerValue(n) => Some(*n as f64),
+        _ => None,
+    })
+}
+
 fn extract_string_array(
     payload: &HashMap<String, qdrant_client::qdrant::Value>,
     key: &str,
@@ -299,3 +453,55 @@ fn extract_string_array(
         })
         .unwrap_or_default()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qdrant_client::qdrant::condition::ConditionOneOf;
+
+    fn chunk(ordinal: usize, score: f32) -> SearchResult {
+        serde_json::from_value(serde_json::json!({
+            "id": ordinal.to_string(), "score": score, "doc_id": "doc::1", "source_id": "a.md",
+            "chunk_id": format!("c{}", ordinal), "text": "", "span": [0, 0], "kinds": [],
+            "ordinal": ordinal,
+        }))
+        .unwrap()
+    }
+
+    fn ordinal_range(filter: &Filter) -> (Option<f64>, Option<f64>) {
+        let range = filter.must.iter().find_map(|c| match &c.condition_one_of {
+            Some(ConditionOneOf::Field(f)) if f.key == "ordinal" => f.range,
+            _ => None,
+        });
+        let range = range.unwrap();
+        (range.gte, range.lte)
+    }
+
+    #[test]
+    fn test_neighbor_filter_at_document_edges() {
+        // The first chunk: the window does not go below 0
+        assert_eq!(ordinal_range(&neighbor_filter("doc::1", 0, 2)), (Some(0.0), Some(2.0)));
+        assert_eq!(ordinal_range(&neighbor_filter("doc::1", 1, 3)), (Some(0.0), Some(4.0)));
+        assert_eq!(ordinal_range(&neighbor_filter("doc::1", 7, 2)), (Some(5.0), Some(9.0)));
+        assert!(matches!(
+            &neighbor_filter("doc::1", 7, 2).must[0].condition_one_of,
+            Some(ConditionOneOf::Field(f)) if f.key == "doc_id"
+        ));
+    }
+
+    #[test]
+    fn test_merge_neighbors_keeps_hit_and_order() {
+        // The last chunk of a 10-chunk document: only earlier neighbours exist
+        let hit = chunk(9, 0.8);
+        let merged = merge_neighbors(&hit, vec![chunk(9, 0.0), chunk(7, 0.0), chunk(8, 0.0)]);
+        let ordinals: Vec<_> = merged.iter().map(|r| r.ordinal).collect();
+        assert_eq!(ordinals, vec![Some(7), Some(8), Some(9)]);
+        assert_eq!(merged[2].score, 0.8);
+
+        // The hit is added when the scroll did not return it
+        let hit = chunk(0, 0.5);
+        let merged = merge_neighbors(&hit, vec![chunk(1, 0.0)]);
+        assert_eq!(merged.iter().map(|r| r.ordinal).collect::<Vec<_>>(), vec![Some(0), Some(1)]);
+        assert_eq!(merged[0].score, 0.5);
+    }
+}
@@ -21,7 +21,7 @@ use axum::{
 };
 use bytes::Bytes;
 use chardetng::EncodingDetector;
-use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use hybrid_rag::extract::{decode_to_utf8_hard, UnsupportedContent};
 use http_body_util::BodyExt;
 
 // Универсальный экстрактор JSON из "любых байт" (любая кодировка → UTF-8 → serde_json)
@@ -200,7 +200,15 @@ where
         .ingest_file(name, file_bytes, lang, title)
         .await
         .map(JsonUtf)
-        .map_err(|e| (StatusCode::BAD_GATEWAY, e.to_string()))
+        .map_err(|e| {
+            // Неподдерживаемый тип файла — ошибка клиента, а не бэкенда
+            let status = if e.downcast_ref::<UnsupportedContent>().is_some() {
+                StatusCode::UNSUPPORTED_MEDIA_TYPE
+            } else {
+                StatusCode::BAD_GATEWAY
+            };
+            (status, e.to_string())
+        })
 }
 
 pub async fn search<P>(
@@ -253,49 +261,6 @@ where
     Ok(JsonUtf(json!({ "config": masked })))
 }
 
-/// Жёсткая декодировка любых байтов в UTF-8:
-/// 1) BOM → используем её;
-/// 2) пробуем как UTF-8 напрямую;
-/// 3) эвристика UTF-16 без BOM (по нулям) → LE/BE;
-/// 4) chardet → encoding_rs;
-fn decode_to_utf8_hard(bytes: &[u8]) -> String {
-    if bytes.is_empty() {
-        return String::new();
-    }
-
-    // 1) BOM?
-    if let Some((enc, off)) = Encoding::for_bom(bytes) {
-        let (cow, _, _) = enc.decode(&bytes[off..]);
-        return cow.into_owned();
-    }
-
-    // 2) Чистый UTF-8?
-    if let Ok(s) = std::str::from_utf8(bytes) {
-        return s.to_string();
-    }
-
-    // 3) Эвристика UTF-16 без BOM (много нулей)
-    let nul_ratio = bytes.iter().filter(|&&b| b == 0).count() as f32 / bytes.len() as f32;
-    if nul_ratio > 0.2 {
-        // Определим эндиянность: если bytes[0]==0 и bytes[1]!=0 — похоже на BE, иначе LE
-        let enc =
-            if bytes.get(0).copied().unwrap_or(0) == 0 && bytes.get(1).copied().unwrap_or(1) != 0 {
-                UTF_16BE
-            } else {
-                UTF_16LE
-            };
-        let (cow, _, _) = enc.decode(bytes);
-        return cow.into_owned();
-    }
-
-    // 4) Детект кодировки → decode
-    let mut det = EncodingDetector::new();
-    det.feed(bytes, true);
-    let enc = det.guess(None, true);
-    let (cow, _, _) = enc.decode(bytes);
-    cow.into_owned()
-}
-
 fn normalize_text(s: &str) -> String {
     let mut out = s.replace("\r\n", "\n").replace('\r', "\n");
     // опционально подчистим нули/непечатаемые границы
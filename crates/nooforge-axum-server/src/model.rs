@@ -7,6 +7,18 @@ pub struct Chunk {
     pub title: Option<String>,
     pub kind: Option<String>,
     pub span: Option<(u64, u64)>,
+    /// Строки начала/конца (с 1)
+    #[serde(default)]
+    pub lines: Option<(u64, u64)>,
+    /// Колонки начала/конца в символах (с 1)
+    #[serde(default)]
+    pub columns: Option<(u64, u64)>,
+    /// Смещения в Unicode-символах (для подсветки без байтов)
+    #[serde(default)]
+    pub char_span: Option<(u64, u64)>,
+    /// Момент в записи, секунды начала/конца (субтитры, расшифровки)
+    #[serde(default)]
+    pub time_span: Option<(f64, f64)>,
     pub preview: Option<String>,
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
 }
@@ -15,6 +27,21 @@ pub struct Chunk {
 pub struct IngestResult {
     pub chunks: Vec<Chunk>,
     pub source_id: String,
+    /// Файлы архива (zip/tar/tar.gz): по результату на каждый
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<IngestEntry>,
+}
+
+/// Итог индексации одного файла из архива.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestEntry {
+    pub path: String,
+    /// `archive.zip!/path/inside`
+    pub source_id: String,
+    pub chunks: usize,
+    /// Причина, если файл пропущен или не проиндексирован
+    #[serde(default)]
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
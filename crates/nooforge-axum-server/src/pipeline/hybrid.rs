@@ -8,16 +8,16 @@ use anyhow::{bail, Result};
 use async_trait::async_trait;
 
 use super::Pipeline;
-use crate::model::{Chunk, IngestResult, RagResponse, SearchResult};
+use crate::model::{Chunk, IngestEntry, IngestResult, RagResponse, SearchResult};
 use crate::pipeline::HasConfig;
 use crate::server_config::ServerConfig;
 
 use hybrid_rag::chunking::ChunkingConfig;
+use hybrid_rag::extract::{sniff_content, ContentType};
 use hybrid_rag::ingest::{compute_doc_id, DocumentIndexer};
 use hybrid_rag::llm::{LlmClient, LlmConfig};
 use hybrid_rag::query::{DocumentRetriever, SearchResult as HybridSearchResult};
 
-use chardetng::EncodingDetector;
 
 pub struct HybridPipeline {
     cfg: Arc<ServerConfig>,
@@ -69,11 +69,16 @@ impl HybridPipeline {
         let chunking = ChunkingConfig {
             max_tokens: cfg.hybrid.max_tokens,
             overlap_tokens: cfg.hybrid.overlap_tokens,
+            overlap_across_blocks: cfg.hybrid.overlap_across_blocks,
             approx_chars_per_token: 4.0,
             hard_max_bytes: 96 * 1024,
+            embed_headings: cfg.hybrid.embed_headings,
+            strategy: cfg.hybrid.chunker,
+            semantic_breakpoint_percentile: cfg.hybrid.semantic_breakpoint_percentile,
+            semantic_buffer_size: cfg.hybrid.semantic_buffer_size,
         };
 
-        let indexer = DocumentIndexer::new(
+        let mut indexer = DocumentIndexer::new(
             &qdrant_url,
             model_path_str,
             tokenizer_path_str,
@@ -81,6 +86,9 @@ impl HybridPipeline {
             chunking,
         )
         .await?;
+        indexer.set_record_mapping(cfg.hybrid.records.clone());
+        indexer.set_notebook_config(cfg.hybrid.notebook.clone());
+        indexer.set_archive_limits(cfg.hybrid.archive);
 
         indexer.ensure_collection().await?;
 
@@ -99,22 +107,31 @@ impl HybridPipeline {
         })
     }
 
+    fn map_chunk(r: HybridSearchResult) -> Chunk {
+        let pair = |p: (usize, usize)| (p.0 as u64, p.1 as u64);
+        Chunk {
+            id: r.chunk_id,
+            source: r.source_id,
+            title: r.title.clone().or_else(|| r.headings.last().cloned()),
+            kind: if r.kinds.is_empty() {
+                None
+            } else {
+                Some(r.kinds.join(","))
+            },
+            span: Some(pair(r.span)),
+            lines: r.line_span.map(pair),
+            columns: r.col_span.map(pair),
+            char_span: r.char_span.map(pair),
+            time_span: r.time_span,
+            preview: Some(r.text),
+            created_at: None,
+        }
+    }
+
     fn map_search(results: Vec<HybridSearchResult>) -> SearchResult {
         let chunks = results
             .into_iter()
-            .map(|r| Chunk {
-                id: r.chunk_id,
-                source: r.source_id,
-                title: None,
-                kind: if r.kinds.is_empty() {
-                    None
-                } else {
-                    Some(r.kinds.join(","))
-                },
-                span: Some((r.span.0 as u64, r.span.1 as u64)),
-                preview: Some(r.text),
-                created_at: None,
-            })
+            .map(Self::map_chunk)
             .collect();
         SearchResult { chunks }
     }
@@ -193,21 +210,10 @@ impl Pipeline for HybridPipeline {
         Ok(IngestResult {
             chunks: listed
                 .into_iter()
-                .map(|r| Chunk {
-                    id: r.chunk_id,
-                    source: r.source_id,
-                    title: None,
-                    kind: if r.kinds.is_empty() {
-                        None
-                    } else {
-                        Some(r.kinds.join(","))
-                    },
-                    span: Some((r.span.0 as u64, r.span.1 as u64)),
-                    preview: Some(r.text),
-                    created_at: None,
-                })
+                .map(Self::map_chunk)
                 .collect(),
             source_id,
+            entries: Vec::new(),
         })
     }
 
@@ -236,32 +242,52 @@ impl Pipeline for HybridPipeline {
                     .map(|s| s.to_string())
             })
             .unwrap_or_else(|| compute_doc_id(&bytes));
-        let source_id = format!("{}{}", self.cfg.hybrid.source_prefix, doc_id);
-
-        // 2) Индексация
-        if is_text_like(&name, &bytes) {
-            // «Текстоподобное»: декодируем и индексируем как документ
-            let text = decode_to_utf8_lossy(&bytes);
-            self.indexer
-                .index_document(&doc_id, &source_id, &text)
+        let mut source_id = format!("{}{}", self.cfg.hybrid.source_prefix, doc_id);
+
+        // 2) Индексация: тип файла определяется по содержимому (сигнатуры, BOM).
+        // Записи CSV/JSON, письма и файлы архива — отдельные документы, чанки ищем по их doc_id
+        let mut record_docs: Vec<String> = Vec::new();
+        let mut entries: Vec<IngestEntry> = Vec::new();
+        if let ContentType::Archive(kind) = sniff_content(&name, &bytes) {
+            // zip/tar/tar.gz: каждый файл — документ `archive.zip!/path`
+            source_id = format!("{}{}", self.cfg.hybrid.source_prefix, name);
+            let results = self
+                .indexer
+                .index_archive(&source_id, kind, &bytes, &self.cfg.hybrid.archive)
                 .await?;
+            for r in results {
+                record_docs.extend(r.doc_ids);
+                entries.push(IngestEntry {
+                    path: r.path,
+                    source_id: r.source_id,
+                    chunks: r.chunks,
+                    error: r.error,
+                });
+            }
+            if record_docs.is_empty() {
+                // Nothing to list: the entries carry the reasons
+                tracing::warn!("archive {} has no indexable files", name);
+                return Ok(IngestResult {
+                    chunks: Vec::new(),
+                    source_id,
+                    entries,
+                });
+            }
         } else {
-            // «Бинарное»: сохраняем во временную папку и индексируем директорию
-            let dir = tempfile::tempdir()?;
-            let path = dir.path().join(&name);
-            std::fs::write(&path, &bytes)?;
-            self.indexer
-                .index_directory(dir.path().to_str().unwrap(), &source_id)
+            // PDF/DOCX/ODT/EPUB, почта, записи, ноутбуки, субтитры, HTML, текст;
+            // неподдерживаемый тип — ошибка UnsupportedContent
+            let indexed = self
+                .indexer
+                .index_bytes(&name, &doc_id, &source_id, &bytes)
                 .await?;
+            record_docs = indexed.into_iter().map(|(doc_id, _)| doc_id).collect();
         }
 
         // 3) Достаём чанки СРАЗУ, как это делается в ingest_text
         let mut listed = vec![];
 
-        if let Ok(res) = self.retriever.search_in_document("*", &doc_id, 100).await {
-            if !res.is_empty() {
-                listed = res;
-            }
+        if let Ok(res) = self.retriever.search_in_documents("*", &record_docs, 100).await {
+            listed = res;
         }
         if listed.is_empty() {
             // Фолбэк: иногда поисковый ключ — source_id
@@ -279,21 +305,10 @@ impl Pipeline for HybridPipeline {
         Ok(IngestResult {
             chunks: listed
                 .into_iter()
-                .map(|r| Chunk {
-                    id: r.chunk_id,
-                    source: r.source_id,
-                    title: None,
-                    kind: if r.kinds.is_empty() {
-                        None
-                    } else {
-                        Some(r.kinds.join(","))
-                    },
-                    span: Some((r.span.0 as u64, r.span.1 as u64)),
-                    preview: Some(r.text),
-                    created_at: None,
-                })
+                .map(Self::map_chunk)
                 .collect(),
             source_id,
+            entries,
         })
     }
 
@@ -416,39 +431,3 @@ impl Pipeline for HybridPipeline {
         anyhow::bail!("All LLM models failed after retries")
     }
 }
-
-/// Пытается угадать кодировку и вернуть текст в UTF-8.
-fn decode_to_utf8_lossy(bytes: &[u8]) -> String {
-    let mut det = EncodingDetector::new();
-    det.feed(bytes, true);
-    let enc = det.guess(None, true);
-    let (cow, _, _) = enc.decode(bytes);
-    cow.into_owned()
-}
-
-/// Эвристика: определяем, похож ли файл на текст.
-fn is_text_like(name: &str, sample: &[u8]) -> bool {
-    let ext = std::path::Path::new(name)
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("")
-        .to_ascii_lowercase();
-
-    // по расширению
-    if matches!(
-        ext.as_str(),
-        "txt" | "md" | "csv" | "json" | "yaml" | "yml" | "html" | "htm" | "rtf" | "log"
-    ) {
-        return true;
-    }
-
-    // по содержимому — если не более 10% байтов «непечатаемые»
-    let non_text = sample
-        .iter()
-        .filter(|&&b| {
-            (b < 0x09 || (b > 0x0D && b < 0x20)) && b != 0x1B // esc seq
-        })
-        .count();
-    let ratio = non_text as f32 / sample.len().max(1) as f32;
-    ratio < 0.1
-}
@@ -1,4 +1,7 @@
 // file: src/server_config.rs
+use hybrid_rag::chunking::ChunkingStrategy;
+use hybrid_rag::config::{NotebookConfig, RecordMapping};
+use hybrid_rag::extract::ArchiveLimits;
 use sha2::{Digest, Sha256};
 use std::env;
 
@@ -49,6 +52,14 @@ pub struct HybridSection {
     pub tokenizer_path: String, // из HYBRID_TOKENIZER_PATH
     pub max_tokens: usize,      // для чанкинга
     pub overlap_tokens: usize,  // для чанкинга
+    pub overlap_across_blocks: bool, // перекрытие между соседними блоками
+    pub embed_headings: bool,   // путь заголовков в тексте для эмбеддинга
+    pub chunker: ChunkingStrategy, // стратегия чанкинга
+    pub semantic_breakpoint_percentile: f32, // semantic: порог разрыва (перцентиль)
+    pub semantic_buffer_size: usize, // semantic: соседних предложений в окне
+    pub records: RecordMapping,    // поля CSV/JSON/JSONL: текст, метаданные, id
+    pub notebook: NotebookConfig,  // .ipynb: индексировать ли выводы ячеек
+    pub archive: ArchiveLimits,    // лимиты распаковки zip/tar/tar.gz и .gz
     pub qdrant_host: String,
     pub qdrant_port: u16,
     pub qdrant_collection: String,
@@ -152,6 +163,37 @@ impl ServerConfig {
             source_prefix: get_env_or_warn("HYBRID_SOURCE_PREFIX", "file://"),
             max_tokens: get_env_num_or_warn("HYBRID_CHUNK_MAX_TOKENS", 350),
             overlap_tokens: get_env_num_or_warn("HYBRID_CHUNK_OVERLAP", 60),
+            overlap_across_blocks: get_env_bool_or_warn("HYBRID_CHUNK_OVERLAP_ACROSS_BLOCKS", false),
+            embed_headings: get_env_bool_or_warn("HYBRID_EMBED_HEADINGS", true),
+            chunker: get_env_num_or_warn("HYBRID_CHUNKER", ChunkingStrategy::Blocks),
+            semantic_breakpoint_percentile: get_env_num_or_warn(
+                "HYBRID_SEMANTIC_BREAKPOINT_PERCENTILE",
+                95.0,
+            ),
+            semantic_buffer_size: get_env_num_or_warn("HYBRID_SEMANTIC_BUFFER_SIZE", 1),
+            records: RecordMapping {
+                enabled: get_env_bool_or_warn("HYBRID_RECORDS", false),
+                text_fields: get_env_list("HYBRID_RECORD_TEXT_FIELDS"),
+                meta_fields: get_env_list("HYBRID_RECORD_META_FIELDS"),
+                id_field: env::var("HYBRID_RECORD_ID_FIELD")
+                    .ok()
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty()),
+            },
+            notebook: NotebookConfig {
+                include_outputs: get_env_bool_or_warn("HYBRID_NOTEBOOK_OUTPUTS", false),
+            },
+            archive: ArchiveLimits {
+                max_entries: get_env_num_or_warn("HYBRID_ARCHIVE_MAX_ENTRIES", 10_000),
+                max_entry_bytes: get_env_num_or_warn(
+                    "HYBRID_ARCHIVE_MAX_ENTRY_BYTES",
+                    64 * 1024 * 1024,
+                ),
+                max_total_bytes: get_env_num_or_warn(
+                    "HYBRID_ARCHIVE_MAX_TOTAL_BYTES",
+                    512 * 1024 * 1024,
+                ),
+            },
         };
 
         Ok(Self {
@@ -178,9 +220,10 @@ impl ServerConfig {
         );
         tracing::info!("HTTP {}:{}", self.http.bind_addr, self.http.bind_port);
         tracing::info!(
-            "Hybrid model_dir='{}' tokenizer='{}' chunk.max={} chunk.overlap={}",
+            "Hybrid model_dir='{}' tokenizer='{}' chunker={} chunk.max={} chunk.overlap={}",
             self.hybrid.model_dir,
             self.hybrid.tokenizer_path,
+            self.hybrid.chunker,
             self.hybrid.max_tokens,
             self.hybrid.overlap_tokens
         );
@@ -288,6 +331,18 @@ fn get_env_or_warn(name: &str, default: impl Into<String>) -> String {
     }
 }
 
+/// Необязательный список через запятую (пусто, если ENV не задан).
+fn get_env_list(name: &str) -> Vec<String> {
+    env::var(name)
+        .map(|v| {
+            v.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 fn get_env_num_or_warn<T>(name: &str, default: T) -> T
 where
     T: std::str::FromStr + Copy,
@@ -307,6 +362,31 @@ where
     }
 }
 
+/// Флаг: 1/0, true/false, yes/no, on/off (регистр не важен).
+fn get_env_bool_or_warn(name: &str, default: bool) -> bool {
+    match std::env::var(name) {
+        Ok(v) => match parse_bool(&v) {
+            Some(x) => x,
+            None => {
+                tracing::warn!("ENV `{}` invalid value `{}`, using default", name, v);
+                default
+            }
+        },
+        Err(_) => {
+            tracing::warn!("ENV `{}` not set, using default", name);
+            default
+        }
+    }
+}
+
+fn parse_bool(v: &str) -> Option<bool> {
+    match v.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
 /// СЕКРЕТЫ — если нет: **сразу ошибка**
 fn require_secret(name: &str) -> AnyResult<String> {
     match std::env::var(name) {